@@ -0,0 +1,53 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use lox_rs::Scanner;
+use std::hint::black_box;
+
+const PROGRAMS: [(&str, &str); 7] = [
+    ("fib", include_str!("lox/fib.lox")),
+    ("binary_trees", include_str!("lox/binary_trees.lox")),
+    ("string_equality", include_str!("lox/string_equality.lox")),
+    ("string_building", include_str!("lox/string_building.lox")),
+    ("method_dispatch", include_str!("lox/method_dispatch.lox")),
+    ("map_stress", include_str!("lox/map_stress.lox")),
+    ("zoo", include_str!("lox/zoo.lox")),
+];
+
+/// Builds a large source by concatenating the benchmark programs until it reaches `size` bytes.
+fn generate_source(size: usize) -> String {
+    let mut source = String::with_capacity(size);
+    for (_, program) in PROGRAMS.iter().cycle() {
+        if source.len() >= size {
+            break;
+        }
+        source.push_str(program);
+    }
+    source
+}
+
+fn scan(source: &str) -> usize {
+    let mut scanner = Scanner::new(source);
+    scanner.scan_tokens().len()
+}
+
+fn bench_programs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan_program");
+    for (name, program) in PROGRAMS {
+        group.throughput(Throughput::Bytes(program.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), program, |b, source| {
+            b.iter(|| scan(black_box(source)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_large_source(c: &mut Criterion) {
+    let source = generate_source(1024 * 1024);
+    let mut group = c.benchmark_group("scan_large");
+    group.throughput(Throughput::Bytes(source.len() as u64));
+    group.sample_size(20);
+    group.bench_function("1mib", |b| b.iter(|| scan(black_box(&source))));
+    group.finish();
+}
+
+criterion_group!(benches, bench_programs, bench_large_source);
+criterion_main!(benches);
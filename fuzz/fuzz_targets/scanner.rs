@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lox_rs::{Scanner, TokenType};
+
+fuzz_target!(|data: &[u8]| {
+    // The scanner works on source text, invalid UTF-8 is rejected before it
+    let Ok(source) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let mut scanner = Scanner::new(source);
+    let tokens = scanner.scan_tokens();
+    assert_eq!(Some(&TokenType::Eof), tokens.last().map(|t| &t.token_type));
+});
@@ -0,0 +1,76 @@
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+
+/// A set of SHA-256 hashes of scripts that are permitted to run.
+///
+/// The file format is compatible with the output of `sha256sum`: one hash per
+/// line, optionally followed by the file name. Blank lines and lines starting
+/// with `#` are ignored.
+pub struct Allowlist {
+    hashes: HashSet<String>,
+}
+
+impl Allowlist {
+    pub fn load(file_path: &str) -> Result<Self, Box<dyn Error>> {
+        let content = fs::read_to_string(file_path)?;
+        Self::parse(&content)
+    }
+
+    fn parse(content: &str) -> Result<Self, Box<dyn Error>> {
+        let mut hashes = HashSet::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let hash = line.split_whitespace().next().unwrap_or_default();
+            if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("[line {}] Invalid SHA-256 hash '{hash}'.", index + 1).into());
+            }
+            hashes.insert(hash.to_ascii_lowercase());
+        }
+        Ok(Self { hashes })
+    }
+
+    pub fn permits(&self, source: &[u8]) -> bool {
+        self.hashes.contains(&hash(source))
+    }
+}
+
+pub fn hash(source: &[u8]) -> String {
+    Sha256::digest(source)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELLO_HASH: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(HELLO_HASH, hash(b"hello"));
+    }
+
+    #[test]
+    fn test_permits() {
+        let content = format!(
+            "# vetted scripts\n\n{}  hello.lox\n",
+            HELLO_HASH.to_uppercase()
+        );
+        let allowlist = Allowlist::parse(&content).unwrap();
+        assert!(allowlist.permits(b"hello"));
+        assert!(!allowlist.permits(b"hello!"));
+    }
+
+    #[test]
+    fn test_invalid_hash() {
+        assert!(Allowlist::parse("not-a-hash").is_err());
+    }
+}
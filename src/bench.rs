@@ -0,0 +1,192 @@
+use lox_rs::Scanner;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Directory of the benchmark programs, relative to the repository root.
+pub const SUITE_DIR: &str = "benches/lox";
+
+/// Slowdown against the baseline above which a program counts as a regression.
+const REGRESSION_THRESHOLD: f64 = 1.10;
+
+/// Number of batches each program is timed in.
+const BATCHES: u32 = 10;
+
+/// Minimum duration of a batch, so that short programs are timed over many scans.
+const MIN_BATCH_DURATION: Duration = Duration::from_millis(20);
+
+/// Time of one benchmark program per phase.
+#[derive(Debug, PartialEq)]
+pub struct BenchResult {
+    pub name: String,
+    pub bytes: usize,
+    pub phase: String,
+    /// Mean time per run in the fastest batch, which is the least disturbed by noise
+    pub time: Duration,
+}
+
+/// Times scanning each of `file_paths`. Running the programs is added once lox-rs can.
+pub fn run_suite(file_paths: &[PathBuf]) -> Result<Vec<BenchResult>, Box<dyn Error>> {
+    let mut results = Vec::new();
+    for file_path in file_paths {
+        let source = fs::read_to_string(file_path)?;
+        let name = file_path.file_stem().map_or_else(
+            || file_path.display().to_string(),
+            |stem| stem.to_string_lossy().to_string(),
+        );
+        results.push(BenchResult {
+            name,
+            bytes: source.len(),
+            phase: "scanning".to_string(),
+            time: time_scan(&source),
+        });
+    }
+    Ok(results)
+}
+
+fn time_scan(source: &str) -> Duration {
+    // The first batch only warms up caches and the allocator
+    (0..=BATCHES)
+        .map(|_| {
+            let started = Instant::now();
+            let mut iterations = 0;
+            while iterations < 10 || started.elapsed() < MIN_BATCH_DURATION {
+                Scanner::new(source).scan_tokens();
+                iterations += 1;
+            }
+            started.elapsed() / iterations
+        })
+        .skip(1)
+        .min()
+        .unwrap_or_default()
+}
+
+/// Renders the results as JSON, one program per line.
+pub fn to_json(results: &[BenchResult]) -> String {
+    let programs: Vec<String> = results
+        .iter()
+        .map(|result| {
+            format!(
+                "    {{\"name\": \"{}\", \"bytes\": {}, \"phase\": \"{}\", \"time_ns\": {}}}",
+                escape(&result.name),
+                result.bytes,
+                escape(&result.phase),
+                result.time.as_nanos()
+            )
+        })
+        .collect();
+    format!("{{\n  \"programs\": [\n{}\n  ]\n}}\n", programs.join(",\n"))
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Reads results written by [`to_json`].
+pub fn parse_json(json: &str) -> Vec<BenchResult> {
+    json.lines().filter_map(parse_program).collect()
+}
+
+fn parse_program(line: &str) -> Option<BenchResult> {
+    Some(BenchResult {
+        name: string_field(line, "name")?,
+        bytes: number_field(line, "bytes")?.try_into().ok()?,
+        phase: string_field(line, "phase")?,
+        time: Duration::from_nanos(number_field(line, "time_ns")?),
+    })
+}
+
+fn string_field(line: &str, name: &str) -> Option<String> {
+    let start = line.find(&format!("\"{name}\": \""))? + name.len() + 5;
+    let mut value = String::new();
+    let mut chars = line[start..].chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '"' => return Some(value),
+            '\\' => value.push(chars.next()?),
+            char => value.push(char),
+        }
+    }
+    None
+}
+
+fn number_field(line: &str, name: &str) -> Option<u64> {
+    let start = line.find(&format!("\"{name}\": "))? + name.len() + 4;
+    let digits: String = line[start..]
+        .chars()
+        .take_while(|char| char.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+/// Compares the results with a baseline, returning a report and whether anything regressed.
+pub fn compare(results: &[BenchResult], baseline: &[BenchResult]) -> (String, bool) {
+    let mut report = format!(
+        "{:<20} {:<10} {:>12} {:>12} {:>8}\n",
+        "program", "phase", "baseline", "current", "change"
+    );
+    let mut regressed = false;
+    for result in results {
+        let Some(base) = baseline
+            .iter()
+            .find(|base| base.name == result.name && base.phase == result.phase)
+        else {
+            report.push_str(&format!(
+                "{:<20} {:<10} {:>12} {:>12.3?}      new\n",
+                result.name, result.phase, "-", result.time
+            ));
+            continue;
+        };
+
+        let ratio = result.time.as_secs_f64() / base.time.as_secs_f64();
+        let marker = if ratio > REGRESSION_THRESHOLD {
+            regressed = true;
+            "  regressed"
+        } else {
+            ""
+        };
+        report.push_str(&format!(
+            "{:<20} {:<10} {:>12.3?} {:>12.3?} {:>+7.1}%{marker}\n",
+            result.name,
+            result.phase,
+            base.time,
+            result.time,
+            (ratio - 1.0) * 100.0
+        ));
+    }
+    (report, regressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(name: &str, micros: u64) -> BenchResult {
+        BenchResult {
+            name: name.to_string(),
+            bytes: 100,
+            phase: "scanning".to_string(),
+            time: Duration::from_micros(micros),
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let results = [result("fib", 12), result("odd \"name\"", 3)];
+        assert_eq!(results.as_slice(), parse_json(&to_json(&results)));
+    }
+
+    #[test]
+    fn test_compare() {
+        let baseline = [result("fib", 100), result("zoo", 100)];
+        let (report, regressed) = compare(&[result("fib", 105)], &baseline);
+        assert!(!regressed);
+        assert!(report.contains("+5.0%"));
+
+        let (report, regressed) = compare(&[result("zoo", 150), result("new", 1)], &baseline);
+        assert!(regressed);
+        assert!(report.contains("+50.0%  regressed"));
+        assert!(report.contains("new"));
+    }
+}
@@ -0,0 +1,397 @@
+use lox_rs::{Scanner, Token, TokenType};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Chapter of the book in which the tests of a directory of the corpus start to pass.
+/// Tests outside these directories are summarized under their directory name.
+const CHAPTERS: [(&str, &str); 31] = [
+    ("scanning", "4 Scanning"),
+    ("unexpected_character", "4 Scanning"),
+    ("expressions/parse", "6 Parsing Expressions"),
+    ("expressions/evaluate", "7 Evaluating Expressions"),
+    ("assignment", "8 Statements and State"),
+    ("block", "8 Statements and State"),
+    ("bool", "8 Statements and State"),
+    ("comments", "8 Statements and State"),
+    ("empty_file", "8 Statements and State"),
+    ("nil", "8 Statements and State"),
+    ("number", "8 Statements and State"),
+    ("operator", "8 Statements and State"),
+    ("precedence", "8 Statements and State"),
+    ("print", "8 Statements and State"),
+    ("string", "8 Statements and State"),
+    ("variable", "8 Statements and State"),
+    ("for", "9 Control Flow"),
+    ("if", "9 Control Flow"),
+    ("logical_operator", "9 Control Flow"),
+    ("while", "9 Control Flow"),
+    ("call", "10 Functions"),
+    ("function", "10 Functions"),
+    ("return", "10 Functions"),
+    ("closure", "11 Resolving and Binding"),
+    ("class", "12 Classes"),
+    ("constructor", "12 Classes"),
+    ("field", "12 Classes"),
+    ("method", "12 Classes"),
+    ("this", "12 Classes"),
+    ("inheritance", "13 Inheritance"),
+    ("super", "13 Inheritance"),
+];
+
+/// Directories of the corpus that hold benchmarks rather than tests.
+const SKIPPED_DIRS: [&str; 1] = ["benchmark"];
+
+/// Unchanged lines shown around each change of an output diff.
+const CONTEXT_LINES: usize = 2;
+
+/// What a test expects, read from its `// expect` and `// Error` annotations.
+#[derive(Debug, Default, PartialEq)]
+pub struct Expectations {
+    pub output: Vec<String>,
+    /// Compile errors as `[line N] Error...`
+    pub errors: Vec<String>,
+    pub runtime_error: Option<String>,
+}
+
+/// Outcome of one test of the corpus.
+pub struct TestResult {
+    pub path: PathBuf,
+    pub chapter: String,
+    pub failure: Option<Failure>,
+}
+
+/// Why a test failed.
+#[derive(Debug, PartialEq)]
+pub enum Failure {
+    /// A missing or unexpected error, described by the first one found
+    Error(String),
+    /// Output that differs from the `// expect:` lines
+    Output {
+        expected: Vec<String>,
+        actual: Vec<String>,
+    },
+}
+
+/// Runs the tests among `file_paths`, which were found in the corpus directory `dir`.
+pub fn run_suite(dir: &Path, file_paths: &[PathBuf]) -> Result<Vec<TestResult>, Box<dyn Error>> {
+    let mut results = Vec::new();
+    for file_path in file_paths {
+        let relative = file_path.strip_prefix(dir).unwrap_or(file_path);
+        if relative.components().next().is_some_and(|dir| {
+            SKIPPED_DIRS
+                .iter()
+                .any(|skipped| dir.as_os_str() == *skipped)
+        }) {
+            continue;
+        }
+        let source = fs::read_to_string(file_path)?;
+        let Some(expectations) = parse_expectations(&source) else {
+            continue;
+        };
+        results.push(TestResult {
+            path: file_path.clone(),
+            chapter: chapter(relative),
+            failure: run_test(&source, &expectations).err(),
+        });
+    }
+    Ok(results)
+}
+
+/// Reads the annotations of a test, or `None` if the file is marked as not being a test.
+///
+/// Errors annotated for another implementation, like `// [c line 3] Error`, are left out.
+pub fn parse_expectations(source: &str) -> Option<Expectations> {
+    let mut expectations = Expectations::default();
+    for (index, line) in source.lines().enumerate() {
+        if line.contains("// nontest") {
+            return None;
+        }
+
+        if let Some((_, output)) = line.split_once("// expect: ") {
+            expectations.output.push(output.trim_end().to_string());
+        } else if let Some((_, message)) = line.split_once("// expect runtime error: ") {
+            expectations.runtime_error = Some(message.trim_end().to_string());
+        } else if let Some((_, error)) = line.split_once("// Error") {
+            expectations
+                .errors
+                .push(format!("[line {}] Error{}", index + 1, error.trim_end()));
+        } else if let Some((_, annotation)) = line.split_once("// [") {
+            let Some((location, error)) = annotation.split_once("] Error") else {
+                continue;
+            };
+            let line = match location.split_once(' ') {
+                Some(("line", line)) => line,
+                Some(("java", line)) => match line.strip_prefix("line ") {
+                    Some(line) => line,
+                    None => continue,
+                },
+                _ => continue,
+            };
+            expectations
+                .errors
+                .push(format!("[line {line}] Error{}", error.trim_end()));
+        }
+    }
+    Some(expectations)
+}
+
+/// Runs a test, returning how it failed if it did not meet its expectations.
+///
+/// Without a parser and interpreter, a test's output is the tokens of its source, in the
+/// format of the scanning chapter. Like a full run, nothing is output if there are errors.
+pub fn run_test(source: &str, expectations: &Expectations) -> Result<(), Failure> {
+    let mut scanner = Scanner::new(source);
+    scanner.scan_tokens();
+
+    let errors: Vec<String> = scanner
+        .errors()
+        .iter()
+        .map(|error| format!("[line {}] Error: {}", error.line, error.message))
+        .collect();
+    for error in &expectations.errors {
+        if !errors.contains(error) {
+            return Err(Failure::Error(format!("missing error '{error}'")));
+        }
+    }
+    for error in &errors {
+        if !expectations.errors.contains(error) {
+            return Err(Failure::Error(format!("unexpected error '{error}'")));
+        }
+    }
+    if let Some(message) = &expectations.runtime_error {
+        return Err(Failure::Error(format!("missing runtime error '{message}'")));
+    }
+
+    let output: Vec<String> = if errors.is_empty() {
+        scanner.tokens().iter().map(scanned_token).collect()
+    } else {
+        Vec::new()
+    };
+    if output != expectations.output {
+        return Err(Failure::Output {
+            expected: expectations.output.clone(),
+            actual: output,
+        });
+    }
+    Ok(())
+}
+
+/// Renders a line-by-line diff of expected and actual output, marking expected lines
+/// that are missing with `-` and unexpected lines with `+`. Unchanged lines are only
+/// shown near a change.
+pub fn render_diff(expected: &[String], actual: &[String], color: bool) -> String {
+    let lines = diff_lines(expected, actual);
+    let changes: Vec<usize> = (0..lines.len())
+        .filter(|&index| lines[index].0 != ' ')
+        .collect();
+
+    let mut rendered = String::new();
+    let mut skipped = false;
+    for (index, (marker, line)) in lines.iter().enumerate() {
+        if !changes
+            .iter()
+            .any(|change| change.abs_diff(index) <= CONTEXT_LINES)
+        {
+            skipped = true;
+            continue;
+        }
+        if skipped {
+            rendered.push_str("  ...\n");
+            skipped = false;
+        }
+        let line = format!("{marker} {line}");
+        match (color, marker) {
+            (true, '-') => rendered.push_str(&format!("\x1b[31m{line}\x1b[0m\n")),
+            (true, '+') => rendered.push_str(&format!("\x1b[32m{line}\x1b[0m\n")),
+            _ => rendered.push_str(&format!("{line}\n")),
+        }
+    }
+    if skipped {
+        rendered.push_str("  ...\n");
+    }
+    rendered
+}
+
+/// Aligns the lines along their longest common subsequence, each marked with `-`, `+`
+/// or ` ` for unchanged.
+fn diff_lines<'a>(expected: &'a [String], actual: &'a [String]) -> Vec<(char, &'a str)> {
+    // common[i][j] is the length of the longest common subsequence of expected[i..] and actual[j..]
+    let mut common = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            lines.push((' ', expected[i].as_str()));
+            i += 1;
+            j += 1;
+        } else if i < expected.len() && (j == actual.len() || common[i + 1][j] >= common[i][j + 1])
+        {
+            lines.push(('-', expected[i].as_str()));
+            i += 1;
+        } else {
+            lines.push(('+', actual[j].as_str()));
+            j += 1;
+        }
+    }
+    lines
+}
+
+/// Formats a token like the scanning chapter prints it, e.g. `NUMBER 123 123.0`.
+fn scanned_token(token: &Token) -> String {
+    let debug = format!("{:?}", token.token_type);
+    let variant = debug.split('(').next().unwrap_or_default();
+    let mut name = String::new();
+    for (index, char) in variant.char_indices() {
+        if index > 0 && char.is_uppercase() {
+            name.push('_');
+        }
+        name.push(char.to_ascii_uppercase());
+    }
+
+    let literal = match &token.token_type {
+        TokenType::String(value) => value.clone(),
+        // Java prints whole doubles with a fraction
+        TokenType::Number(value) if value.fract() == 0.0 && value.abs() < 1e7 => {
+            format!("{value:.1}")
+        }
+        TokenType::Number(value) => value.to_string(),
+        _ => "null".to_string(),
+    };
+    format!("{name} {} {literal}", token.lexeme)
+        .trim_end()
+        .to_string()
+}
+
+/// Finds the chapter of a test from its path relative to the corpus directory.
+fn chapter(relative: &Path) -> String {
+    let name = relative
+        .with_extension("")
+        .to_string_lossy()
+        .replace('\\', "/");
+    let dir = name.split('/').next().unwrap_or_default();
+    CHAPTERS
+        .iter()
+        .find(|(prefix, _)| *prefix == name || *prefix == dir)
+        .map_or_else(|| dir.to_string(), |(_, chapter)| chapter.to_string())
+}
+
+/// Summarizes the results per chapter, in the order of the book.
+pub fn summarize(results: &[TestResult]) -> String {
+    let mut chapters: Vec<&str> = Vec::new();
+    for (_, chapter) in CHAPTERS {
+        if !chapters.contains(&chapter) {
+            chapters.push(chapter);
+        }
+    }
+    let mut others: Vec<&str> = results
+        .iter()
+        .map(|result| result.chapter.as_str())
+        .filter(|chapter| !chapters.contains(chapter))
+        .collect();
+    others.sort();
+    others.dedup();
+    chapters.extend(others);
+
+    let mut summary = format!("{:<28} {:>6} {:>6}\n", "chapter", "passed", "failed");
+    let mut total = (0, 0);
+    for chapter in chapters {
+        let (passed, failed) = results
+            .iter()
+            .filter(|result| result.chapter == chapter)
+            .fold((0, 0), |(passed, failed), result| match result.failure {
+                Some(_) => (passed, failed + 1),
+                None => (passed + 1, failed),
+            });
+        if passed + failed > 0 {
+            summary.push_str(&format!("{chapter:<28} {passed:>6} {failed:>6}\n"));
+        }
+        total = (total.0 + passed, total.1 + failed);
+    }
+    summary.push_str(&format!("{:<28} {:>6} {:>6}\n", "total", total.0, total.1));
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_expectations() {
+        let source = "print 1; // expect: 1\n\
+                      foo(a | b); // [line 2] Error: Unexpected character.\n\
+                      // [java line 3] Error at 'b': Expect ')' after arguments.\n\
+                      // [c line 3] Error at 'b': Expect expression.\n\
+                      var a = a; // Error at 'a': Can't read local variable.\n\
+                      nil(); // expect runtime error: Can only call functions and classes.\n";
+        assert_eq!(
+            parse_expectations(source),
+            Some(Expectations {
+                output: vec!["1".to_string()],
+                errors: vec![
+                    "[line 2] Error: Unexpected character.".to_string(),
+                    "[line 3] Error at 'b': Expect ')' after arguments.".to_string(),
+                    "[line 5] Error at 'a': Can't read local variable.".to_string(),
+                ],
+                runtime_error: Some("Can only call functions and classes.".to_string()),
+            })
+        );
+        assert_eq!(parse_expectations("// nontest\nprint 1;"), None);
+    }
+
+    #[test]
+    fn test_run_test() {
+        let source = "123.\n\"str\"\n\n\
+                      // expect: NUMBER 123 123.0\n\
+                      // expect: DOT . null\n\
+                      // expect: STRING \"str\" str\n\
+                      // expect: EOF  null\n";
+        let expectations = parse_expectations(source).unwrap();
+        assert_eq!(run_test(source, &expectations), Ok(()));
+
+        let source = "print 1 | 2; // expect: 3\n";
+        let expectations = parse_expectations(source).unwrap();
+        assert_eq!(
+            run_test(source, &expectations),
+            Err(Failure::Error(
+                "unexpected error '[line 1] Error: Unexpected character.'".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_render_diff() {
+        let lines = |text: &str| -> Vec<String> { text.split(' ').map(String::from).collect() };
+        let expected = lines("a b c d e f g h");
+        let actual = lines("a b c d x f g h i");
+        assert_eq!(
+            render_diff(&expected, &actual, false),
+            "  ...\n  c\n  d\n- e\n+ x\n  f\n  g\n  h\n+ i\n"
+        );
+        assert_eq!(
+            render_diff(&expected, &lines("a b c d x f g h"), true),
+            "  ...\n  c\n  d\n\x1b[31m- e\x1b[0m\n\x1b[32m+ x\x1b[0m\n  f\n  g\n  ...\n"
+        );
+    }
+
+    #[test]
+    fn test_chapter() {
+        assert_eq!(chapter(Path::new("scanning/numbers.lox")), "4 Scanning");
+        assert_eq!(chapter(Path::new("unexpected_character.lox")), "4 Scanning");
+        assert_eq!(
+            chapter(Path::new("expressions/parse.lox")),
+            "6 Parsing Expressions"
+        );
+        assert_eq!(chapter(Path::new("regression/40.lox")), "regression");
+    }
+}
@@ -0,0 +1,43 @@
+use std::io::IsTerminal;
+
+/// Decides whether to color the output for a `--color` choice of `auto`, `always` or `never`.
+pub fn use_color(choice: &str) -> Option<bool> {
+    match choice {
+        "auto" => Some(std::io::stdout().is_terminal() && enable_ansi()),
+        "always" => {
+            enable_ansi();
+            Some(true)
+        }
+        "never" => Some(false),
+        _ => None,
+    }
+}
+
+/// Enables ANSI escape processing of the console, returning whether it is supported.
+#[cfg(windows)]
+fn enable_ansi() -> bool {
+    use std::ffi::c_void;
+    use std::os::windows::io::AsRawHandle;
+
+    const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x0004;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetConsoleMode(handle: *mut c_void, mode: *mut u32) -> i32;
+        fn SetConsoleMode(handle: *mut c_void, mode: u32) -> i32;
+    }
+
+    let handle = std::io::stdout().as_raw_handle();
+    let mut mode = 0;
+    // SAFETY: the handle is the process's stdout and `mode` outlives the calls
+    unsafe {
+        GetConsoleMode(handle, &mut mode) != 0
+            && SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0
+    }
+}
+
+/// Terminals outside Windows interpret ANSI escapes natively.
+#[cfg(not(windows))]
+fn enable_ansi() -> bool {
+    true
+}
@@ -0,0 +1,96 @@
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Prints a report for a panic inside lox-rs itself, pointing at the source line being processed.
+pub fn report(phase: &str, source: &str, line: usize, payload: &(dyn Any + Send)) {
+    eprintln!();
+    eprintln!("lox-rs crashed while {phase}. Sorry, this is a bug in lox-rs, not in your script.");
+    eprintln!("Please report it together with the details below.");
+    eprintln!();
+    eprintln!(
+        "version: {} {}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    );
+    eprintln!("phase:   {phase}");
+    eprintln!("panic:   {}", panic_message(payload));
+    if let Some(text) = source.lines().nth(line.saturating_sub(1)) {
+        eprintln!("source:");
+        eprintln!("{line:>5} | {text}");
+    }
+}
+
+/// Reduces `source` to a smaller input that still makes `phase` panic and prints it.
+pub fn report_minimized(source: &str, phase: impl Fn(&str)) {
+    // Keep the panic messages of all the attempts out of the report
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let minimized = minimize(source, |candidate| {
+        panic::catch_unwind(AssertUnwindSafe(|| phase(candidate))).is_err()
+    });
+    panic::set_hook(hook);
+
+    eprintln!();
+    eprintln!("minimized reproducer ({} bytes):", minimized.len());
+    eprintln!("{minimized}");
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "<unknown>"
+    }
+}
+
+/// Removes ever smaller chunks of characters from `source` as long as `fails` keeps holding.
+fn minimize(source: &str, fails: impl Fn(&str) -> bool) -> String {
+    let mut current: Vec<char> = source.chars().collect();
+    let mut chunk = current.len().div_ceil(2);
+
+    while chunk > 0 {
+        let mut reduced = false;
+        let mut start = 0;
+        while start < current.len() {
+            let end = (start + chunk).min(current.len());
+            let candidate: Vec<char> = current[..start]
+                .iter()
+                .chain(&current[end..])
+                .copied()
+                .collect();
+            if fails(&candidate.iter().collect::<String>()) {
+                current = candidate;
+                reduced = true;
+            } else {
+                start += chunk;
+            }
+        }
+
+        if !reduced {
+            chunk /= 2;
+        }
+    }
+    current.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minimize() {
+        let source = "var a = 1;\nprint \"bug\";\nvar b = 2;";
+        let minimized = minimize(source, |candidate| candidate.contains("bug"));
+        assert_eq!("bug", minimized);
+    }
+
+    #[test]
+    fn test_minimize_keeps_required_parts() {
+        let minimized = minimize("x + y * z", |candidate| {
+            candidate.contains('x') && candidate.contains('z')
+        });
+        assert_eq!("xz", minimized);
+    }
+}
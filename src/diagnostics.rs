@@ -0,0 +1,340 @@
+use crate::scanner::ScanError;
+use crate::token::Span;
+use std::fmt::{Display, Formatter};
+use std::str::Utf8Error;
+
+/// Stable code of a diagnostic kind, shown in messages and explained by `lox-rs explain`.
+///
+/// Codes are grouped by phase: `L00xx` for scanning, `L01xx` for parsing and `L02xx`
+/// for resolution and runtime errors. A code is never reused for a different error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorCode {
+    UnexpectedCharacter,
+    UnterminatedString,
+    StringTooLong,
+    IdentifierTooLong,
+    TooManyTokens,
+    InvalidUtf8,
+    UnreadableFile,
+    TooManyErrors,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 8] = [
+        ErrorCode::UnexpectedCharacter,
+        ErrorCode::UnterminatedString,
+        ErrorCode::StringTooLong,
+        ErrorCode::IdentifierTooLong,
+        ErrorCode::TooManyTokens,
+        ErrorCode::InvalidUtf8,
+        ErrorCode::UnreadableFile,
+        ErrorCode::TooManyErrors,
+    ];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorCode::UnexpectedCharacter => "L0001",
+            ErrorCode::UnterminatedString => "L0002",
+            ErrorCode::StringTooLong => "L0003",
+            ErrorCode::IdentifierTooLong => "L0004",
+            ErrorCode::TooManyTokens => "L0005",
+            ErrorCode::InvalidUtf8 => "L0006",
+            ErrorCode::UnreadableFile => "L0007",
+            ErrorCode::TooManyErrors => "L0008",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|error_code| error_code.code().eq_ignore_ascii_case(code))
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            ErrorCode::UnexpectedCharacter => "Unexpected character.",
+            ErrorCode::UnterminatedString => "Unterminated string.",
+            ErrorCode::StringTooLong => "String literal too long.",
+            ErrorCode::IdentifierTooLong => "Identifier too long.",
+            ErrorCode::TooManyTokens => "Too many tokens.",
+            ErrorCode::InvalidUtf8 => "Source is not valid UTF-8.",
+            ErrorCode::UnreadableFile => "Could not read file.",
+            ErrorCode::TooManyErrors => "Too many errors.",
+        }
+    }
+
+    /// A longer description of the error with an example and how to fix it.
+    pub fn explanation(&self) -> &'static str {
+        match self {
+            ErrorCode::UnexpectedCharacter => {
+                "The source contains a character that does not start any Lox token.
+
+Erroneous code example:
+
+    var total = price @ 2;
+
+Lox has no `@` operator. Remove the character or replace it with a valid
+operator, for example `price * 2`. Characters that are not part of the
+language are only allowed inside string literals and comments."
+            }
+            ErrorCode::UnterminatedString => {
+                "A string literal is missing its closing quote, so it runs until the end
+of the file.
+
+Erroneous code example:
+
+    print \"Hello, world!;
+
+Add the closing `\"` where the string is meant to end:
+
+    print \"Hello, world!\";
+
+Strings may span multiple lines. The error is reported at the opening quote,
+which may be many lines before the end of the file."
+            }
+            ErrorCode::StringTooLong => {
+                "A string literal is longer than the scanner accepts. The limit guards
+against unbounded memory use on untrusted input and defaults to 1 MiB.
+
+Build large strings at runtime instead of writing them as one literal, or
+raise the limit with `--max-string-length <bytes>`."
+            }
+            ErrorCode::IdentifierTooLong => {
+                "An identifier is longer than the scanner accepts. The limit defaults to
+1024 bytes.
+
+Use a shorter name, or raise the limit with `--max-identifier-length <bytes>`
+if the source is generated and the names cannot be changed."
+            }
+            ErrorCode::TooManyTokens => {
+                "The source contains more tokens than the scanner accepts, so scanning
+stopped. The limit defaults to one million tokens.
+
+Split the program into smaller files, or raise the limit with
+`--max-tokens <count>`."
+            }
+            ErrorCode::InvalidUtf8 => {
+                "Lox source files must be encoded as UTF-8. The file contains a byte
+sequence that is not valid UTF-8, usually because it was saved in another
+encoding such as Latin-1 or UTF-16, or because it is not a text file.
+
+The error points at the first invalid byte. Convert the file to UTF-8, for
+example with `iconv -f latin1 -t utf-8`."
+            }
+            ErrorCode::UnreadableFile => {
+                "The file could not be opened or read. The note of the diagnostic gives
+the reason reported by the operating system, such as a missing file or
+missing permissions."
+            }
+            ErrorCode::TooManyErrors => {
+                "The source contains more errors than the scanner reports, so scanning
+stopped. The limit defaults to 1000 errors and keeps input that is not Lox
+at all, such as a binary file, from producing unbounded output.
+
+Fix the errors reported so far, or raise the limit with
+`--max-errors <count>`."
+            }
+        }
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// A diagnostic with its full location, for tools that consume lox-rs output.
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Diagnostic {
+    pub code: ErrorCode,
+    pub severity: Severity,
+    pub message: String,
+    pub file: String,
+    pub line: usize,
+    /// 1-based column of the span start, counted in characters
+    pub column: usize,
+    pub span: Span,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    /// Locates `error` at the start of its span, like the error's own line.
+    pub fn from_scan_error(error: &ScanError, file: &str, source: &str) -> Self {
+        let (line, column) = position(source, error.span.start);
+        Self {
+            code: error.code,
+            severity: Severity::Error,
+            message: error.message.clone(),
+            file: file.to_string(),
+            line,
+            column,
+            span: error.span,
+            notes: Vec::new(),
+        }
+    }
+
+    /// Locates the first invalid byte of a file that is not valid UTF-8.
+    pub fn from_utf8_error(error: Utf8Error, file: &str, bytes: &[u8]) -> Self {
+        let start = error.valid_up_to();
+        // The prefix up to the error is valid by definition
+        let valid = std::str::from_utf8(&bytes[..start]).unwrap_or_default();
+        let (line, column) = position(valid, start);
+        let end = start + error.error_len().unwrap_or(bytes.len() - start);
+        Self {
+            code: ErrorCode::InvalidUtf8,
+            severity: Severity::Error,
+            message: ErrorCode::InvalidUtf8.message().to_string(),
+            file: file.to_string(),
+            line,
+            column,
+            span: Span { start, end },
+            notes: Vec::new(),
+        }
+    }
+
+    /// A diagnostic for a file that could not be read, with the reason as a note.
+    pub fn unreadable(error: &std::io::Error, file: &str) -> Self {
+        Self {
+            code: ErrorCode::UnreadableFile,
+            severity: Severity::Error,
+            message: ErrorCode::UnreadableFile.message().to_string(),
+            file: file.to_string(),
+            line: 1,
+            column: 1,
+            span: Span { start: 0, end: 0 },
+            notes: vec![error.to_string()],
+        }
+    }
+
+    /// Renders the diagnostic as a single-line JSON object.
+    pub fn to_json(&self) -> String {
+        let notes: Vec<String> = self.notes.iter().map(|note| json_string(note)).collect();
+        format!(
+            "{{\"code\":{},\"severity\":{},\"message\":{},\"file\":{},\"line\":{},\"column\":{},\"span\":{{\"start\":{},\"end\":{}}},\"notes\":[{}]}}",
+            json_string(self.code.code()),
+            json_string(self.severity.name()),
+            json_string(&self.message),
+            json_string(&self.file),
+            self.line,
+            self.column,
+            self.span.start,
+            self.span.end,
+            notes.join(",")
+        )
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[line {}] Error[{}]: {}",
+            self.line, self.code, self.message
+        )?;
+        for note in &self.notes {
+            write!(f, "\n  note: {note}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The 1-based line and column of the byte `offset` in `source`.
+fn position(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    let line = before.matches('\n').count() + 1;
+    (line, before[line_start..].chars().count() + 1)
+}
+
+fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for char in value.chars() {
+        match char {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            char if char < ' ' => json.push_str(&format!("\\u{:04x}", char as u32)),
+            char => json.push(char),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_are_unique() {
+        for error_code in ErrorCode::ALL {
+            assert_eq!(Some(error_code), ErrorCode::from_code(error_code.code()));
+        }
+    }
+
+    #[test]
+    fn test_from_code() {
+        assert_eq!(
+            Some(ErrorCode::UnexpectedCharacter),
+            ErrorCode::from_code("l0001")
+        );
+        assert_eq!(None, ErrorCode::from_code("L9999"));
+    }
+
+    #[test]
+    fn test_diagnostic_to_json() {
+        let source = "var a;\nvar é = @;";
+        let errors = crate::scan(source).unwrap_err();
+        let diagnostic = Diagnostic::from_scan_error(&errors[0], "dir\\\"a\".lox", source);
+        assert_eq!(9, diagnostic.column);
+        assert_eq!(
+            "{\"code\":\"L0001\",\"severity\":\"error\",\"message\":\"Unexpected character.\",\
+             \"file\":\"dir\\\\\\\"a\\\".lox\",\"line\":2,\"column\":9,\"span\":{\"start\":16,\"end\":17},\"notes\":[]}",
+            diagnostic.to_json()
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_of_multiline_string() {
+        let source = "var a = \"open\nmore\nlines";
+        let errors = crate::scan(source).unwrap_err();
+        let diagnostic = Diagnostic::from_scan_error(&errors[0], "a.lox", source);
+        assert_eq!(ErrorCode::UnterminatedString, diagnostic.code);
+        assert_eq!((1, 9), (diagnostic.line, diagnostic.column));
+    }
+
+    #[test]
+    fn test_diagnostic_of_invalid_utf8() {
+        let bytes = b"print 1;\nvar \xff = 2;".to_vec();
+        let error = String::from_utf8(bytes).unwrap_err();
+        let diagnostic = Diagnostic::from_utf8_error(error.utf8_error(), "a.lox", error.as_bytes());
+        assert_eq!((2, 5), (diagnostic.line, diagnostic.column));
+        assert_eq!(Span { start: 13, end: 14 }, diagnostic.span);
+        assert_eq!(
+            "[line 2] Error[L0006]: Source is not valid UTF-8.",
+            diagnostic.to_string()
+        );
+    }
+}
@@ -0,0 +1,343 @@
+use crate::scanner::Scanner;
+use crate::token::{Token, TokenType};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum DocKind {
+    Function,
+    Class,
+    Method,
+}
+
+/// A documented declaration together with its `///` comment text.
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub struct DocItem {
+    pub kind: DocKind,
+    pub name: String,
+    pub params: Vec<String>,
+    pub superclass: Option<String>,
+    pub docs: String,
+    pub line: usize,
+    /// Documented methods of a class
+    pub methods: Vec<DocItem>,
+}
+
+/// Extracts the top-level functions and classes of `source` that have doc comments.
+pub fn extract(source: &str) -> Vec<DocItem> {
+    declarations(source)
+        .into_iter()
+        .filter_map(|mut item| {
+            item.methods.retain(|method| !method.docs.is_empty());
+            (!item.docs.is_empty() || !item.methods.is_empty()).then_some(item)
+        })
+        .collect()
+}
+
+/// Extracts all top-level functions and classes of `source`, with all methods.
+///
+/// Declarations are recognized from the token stream, so the source does not need to
+/// be free of errors elsewhere.
+pub fn declarations(source: &str) -> Vec<DocItem> {
+    let mut scanner = Scanner::new(source).with_trivia(true);
+    let tokens = significant_tokens(scanner.scan_tokens());
+
+    let mut items = Vec::new();
+    let mut current = 0;
+    while current < tokens.len() {
+        let (token, docs) = &tokens[current];
+        let item = match token.token_type {
+            TokenType::Fun => function(&tokens, current + 1, DocKind::Function, docs),
+            TokenType::Class => class(&tokens, current, docs),
+            _ => None,
+        };
+
+        match token.token_type {
+            TokenType::LeftBrace => current = skip_block(&tokens, current),
+            _ => current += 1,
+        }
+        items.extend(item);
+    }
+    items
+}
+
+/// Drops trivia, attaching the text of preceding `///` comments to each remaining token.
+fn significant_tokens(tokens: &[Token]) -> Vec<(&Token, String)> {
+    let mut significant = Vec::new();
+    let mut docs: Vec<&str> = Vec::new();
+    for token in tokens {
+        match token.token_type {
+            TokenType::Comment => match token.lexeme.strip_prefix("///") {
+                Some(text) if !text.starts_with('/') => {
+                    docs.push(text.strip_prefix(' ').unwrap_or(text).trim_end());
+                }
+                _ => docs.clear(),
+            },
+            TokenType::Whitespace | TokenType::Newline => {}
+            _ => significant.push((token, std::mem::take(&mut docs).join("\n"))),
+        }
+    }
+    significant
+}
+
+/// Parses `name(params)` starting at `start`.
+fn function(
+    tokens: &[(&Token, String)],
+    start: usize,
+    kind: DocKind,
+    docs: &str,
+) -> Option<DocItem> {
+    let name = identifier(tokens, start)?;
+    let (left_paren, _) = tokens.get(start + 1)?;
+    if left_paren.token_type != TokenType::LeftParen {
+        return None;
+    }
+
+    let params = tokens[start + 2..]
+        .iter()
+        .take_while(|(token, _)| token.token_type != TokenType::RightParen)
+        .filter_map(|(token, _)| match &token.token_type {
+            TokenType::Identifier(param) => Some(param.clone()),
+            _ => None,
+        })
+        .collect();
+
+    Some(DocItem {
+        kind,
+        name,
+        params,
+        superclass: None,
+        docs: docs.to_string(),
+        line: tokens[start].0.line,
+        methods: Vec::new(),
+    })
+}
+
+/// Parses `class Name [< Superclass] { methods }` starting at the `class` keyword.
+fn class(tokens: &[(&Token, String)], start: usize, docs: &str) -> Option<DocItem> {
+    let name = identifier(tokens, start + 1)?;
+    let mut current = start + 2;
+    let mut superclass = None;
+    if tokens.get(current)?.0.token_type == TokenType::Less {
+        superclass = Some(identifier(tokens, current + 1)?);
+        current += 2;
+    }
+    if tokens.get(current)?.0.token_type != TokenType::LeftBrace {
+        return None;
+    }
+
+    let end = skip_block(tokens, current) - 1;
+    current += 1;
+    let mut methods = Vec::new();
+    while current < end {
+        let (token, method_docs) = &tokens[current];
+        if let TokenType::Identifier(_) = token.token_type {
+            methods.extend(function(tokens, current, DocKind::Method, method_docs));
+        }
+        match token.token_type {
+            TokenType::LeftBrace => current = skip_block(tokens, current),
+            _ => current += 1,
+        }
+    }
+
+    Some(DocItem {
+        kind: DocKind::Class,
+        name,
+        params: Vec::new(),
+        superclass,
+        docs: docs.to_string(),
+        line: tokens[start].0.line,
+        methods,
+    })
+}
+
+fn identifier(tokens: &[(&Token, String)], index: usize) -> Option<String> {
+    match &tokens.get(index)?.0.token_type {
+        TokenType::Identifier(name) => Some(name.clone()),
+        _ => None,
+    }
+}
+
+/// Returns the index after the brace that closes the block opened at `start`.
+fn skip_block(tokens: &[(&Token, String)], start: usize) -> usize {
+    let mut depth = 0;
+    for (index, (token, _)) in tokens.iter().enumerate().skip(start) {
+        match token.token_type {
+            TokenType::LeftBrace => depth += 1,
+            TokenType::RightBrace => {
+                depth -= 1;
+                if depth == 0 {
+                    return index + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+/// Renders the extracted items as a Markdown document titled `title`.
+pub fn to_markdown(title: &str, items: &[DocItem]) -> String {
+    let mut markdown = format!("# {title}\n");
+    for item in items {
+        render_item(&mut markdown, item, "##");
+        for method in &item.methods {
+            render_item(&mut markdown, method, "###");
+        }
+    }
+    markdown
+}
+
+/// Renders the classes among `items` as a tree, each subclass below its superclass.
+pub fn class_hierarchy(items: &[DocItem]) -> String {
+    let classes: Vec<&DocItem> = items
+        .iter()
+        .filter(|item| item.kind == DocKind::Class)
+        .collect();
+    let is_declared = |name: &str| classes.iter().any(|class| class.name == name);
+
+    let mut tree = String::new();
+    let mut rendered = Vec::new();
+    // Classes whose superclass is not declared in the program start a tree. Any class
+    // left over afterwards is part of an inheritance cycle and starts one as well.
+    let roots = classes
+        .iter()
+        .filter(|class| !class.superclass.as_deref().is_some_and(is_declared))
+        .chain(&classes);
+    for root in roots {
+        render_class(&mut tree, root, &classes, 0, &mut rendered);
+    }
+    tree
+}
+
+fn render_class<'a>(
+    tree: &mut String,
+    class: &'a DocItem,
+    classes: &[&'a DocItem],
+    depth: usize,
+    rendered: &mut Vec<&'a str>,
+) {
+    if rendered.contains(&class.name.as_str()) {
+        return;
+    }
+    rendered.push(&class.name);
+
+    let indent = "  ".repeat(depth);
+    match &class.superclass {
+        Some(superclass) => {
+            tree.push_str(&format!("{indent}class {} < {superclass}\n", class.name))
+        }
+        None => tree.push_str(&format!("{indent}class {}\n", class.name)),
+    }
+    for method in &class.methods {
+        tree.push_str(&format!(
+            "{indent}  {}({})\n",
+            method.name,
+            method.params.join(", ")
+        ));
+    }
+    for subclass in classes
+        .iter()
+        .filter(|subclass| subclass.superclass.as_ref() == Some(&class.name))
+    {
+        render_class(tree, subclass, classes, depth + 1, rendered);
+    }
+}
+
+fn render_item(markdown: &mut String, item: &DocItem, heading: &str) {
+    let signature = match item.kind {
+        DocKind::Function => format!("fun {}({})", item.name, item.params.join(", ")),
+        DocKind::Method => format!("{}({})", item.name, item.params.join(", ")),
+        DocKind::Class => match &item.superclass {
+            Some(superclass) => format!("class {} < {superclass}", item.name),
+            None => format!("class {}", item.name),
+        },
+    };
+    markdown.push_str(&format!("\n{heading} `{signature}`\n"));
+    if !item.docs.is_empty() {
+        markdown.push_str(&format!("\n{}\n", item.docs));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "\
+/// Computes the nth Fibonacci number.
+/// Slowly.
+fun fib(n) {
+  /// Not a declaration
+  fun inner() {}
+  return n;
+}
+
+fun undocumented() {}
+
+// A plain comment
+class Tree < Node {
+  /// Creates a tree.
+  init(item, depth) {
+    if (depth > 0) { this.left = nil; }
+  }
+
+  check() {}
+}
+";
+
+    #[test]
+    fn test_extract() {
+        let items = extract(SOURCE);
+        assert_eq!(2, items.len());
+
+        assert_eq!(DocKind::Function, items[0].kind);
+        assert_eq!("fib", items[0].name);
+        assert_eq!(vec!["n"], items[0].params);
+        assert_eq!("Computes the nth Fibonacci number.\nSlowly.", items[0].docs);
+        assert_eq!(3, items[0].line);
+
+        assert_eq!(DocKind::Class, items[1].kind);
+        assert_eq!("Tree", items[1].name);
+        assert_eq!(Some("Node".to_string()), items[1].superclass);
+        assert_eq!("", items[1].docs);
+        assert_eq!(1, items[1].methods.len());
+        assert_eq!("init", items[1].methods[0].name);
+        assert_eq!(vec!["item", "depth"], items[1].methods[0].params);
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let markdown = to_markdown("tree.lox", &extract(SOURCE));
+        assert_eq!(
+            "# tree.lox\n\
+             \n## `fun fib(n)`\n\nComputes the nth Fibonacci number.\nSlowly.\n\
+             \n## `class Tree < Node`\n\
+             \n### `init(item, depth)`\n\nCreates a tree.\n",
+            markdown
+        );
+    }
+
+    #[test]
+    fn test_class_hierarchy() {
+        let source = "\
+class Node {}
+class Tree < Node { init(item, depth) {} check() {} }
+class Leaf < Tree {}
+class Widget < Base { draw() {} }
+class A < B {}
+class B < A {}
+";
+        assert_eq!(
+            "class Node\n\
+             \x20 class Tree < Node\n\
+             \x20   init(item, depth)\n\
+             \x20   check()\n\
+             \x20   class Leaf < Tree\n\
+             class Widget < Base\n\
+             \x20 draw()\n\
+             class A < B\n\
+             \x20 class B < A\n",
+            class_hierarchy(&declarations(source))
+        );
+    }
+}
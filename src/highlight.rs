@@ -0,0 +1,223 @@
+use crate::scanner::Scanner;
+use crate::token::{Span, TokenType};
+
+/// Semantic class of a piece of source, as used by highlighters and editors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum HighlightClass {
+    Keyword,
+    Number,
+    String,
+    Identifier,
+    Comment,
+    Operator,
+    Punctuation,
+}
+
+impl HighlightClass {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HighlightClass::Keyword => "keyword",
+            HighlightClass::Number => "number",
+            HighlightClass::String => "string",
+            HighlightClass::Identifier => "identifier",
+            HighlightClass::Comment => "comment",
+            HighlightClass::Operator => "operator",
+            HighlightClass::Punctuation => "punctuation",
+        }
+    }
+
+    fn ansi_color(&self) -> Option<&'static str> {
+        match self {
+            HighlightClass::Keyword => Some("\x1b[35m"),
+            HighlightClass::Number => Some("\x1b[36m"),
+            HighlightClass::String => Some("\x1b[32m"),
+            HighlightClass::Comment => Some("\x1b[90m"),
+            HighlightClass::Operator => Some("\x1b[33m"),
+            HighlightClass::Identifier | HighlightClass::Punctuation => None,
+        }
+    }
+}
+
+/// A classified span of the source.
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Highlight {
+    pub span: Span,
+    pub class: HighlightClass,
+}
+
+/// The highlight class of a token type, `None` for whitespace and the end of input.
+pub fn classify(token_type: &TokenType) -> Option<HighlightClass> {
+    let class = match token_type {
+        TokenType::LeftParen
+        | TokenType::RightParen
+        | TokenType::LeftBrace
+        | TokenType::RightBrace
+        | TokenType::LeftBracket
+        | TokenType::RightBracket
+        | TokenType::Comma
+        | TokenType::Dot
+        | TokenType::Semicolon => HighlightClass::Punctuation,
+        TokenType::Minus
+        | TokenType::Plus
+        | TokenType::Slash
+        | TokenType::Star
+        | TokenType::Bang
+        | TokenType::BangEqual
+        | TokenType::Equal
+        | TokenType::EqualEqual
+        | TokenType::Greater
+        | TokenType::GreaterEqual
+        | TokenType::Less
+        | TokenType::LessEqual => HighlightClass::Operator,
+        TokenType::Identifier(_) => HighlightClass::Identifier,
+        TokenType::String(_) => HighlightClass::String,
+        TokenType::Number(_) => HighlightClass::Number,
+        TokenType::And
+        | TokenType::Break
+        | TokenType::Case
+        | TokenType::Catch
+        | TokenType::Class
+        | TokenType::Const
+        | TokenType::Continue
+        | TokenType::Default
+        | TokenType::Else
+        | TokenType::False
+        | TokenType::Finally
+        | TokenType::Fun
+        | TokenType::For
+        | TokenType::If
+        | TokenType::Import
+        | TokenType::In
+        | TokenType::Macro
+        | TokenType::Nil
+        | TokenType::Or
+        | TokenType::Print
+        | TokenType::Return
+        | TokenType::Super
+        | TokenType::Switch
+        | TokenType::This
+        | TokenType::Throw
+        | TokenType::True
+        | TokenType::Try
+        | TokenType::Var
+        | TokenType::While => HighlightClass::Keyword,
+        TokenType::Comment => HighlightClass::Comment,
+        TokenType::Whitespace | TokenType::Newline | TokenType::Invalid | TokenType::Eof => {
+            return None
+        }
+    };
+    Some(class)
+}
+
+/// Classifies the tokens and comments of `source`, ordered by position.
+pub fn highlight(source: &str) -> Vec<Highlight> {
+    let mut scanner = Scanner::new(source).with_trivia(true);
+    scanner
+        .scan_tokens()
+        .iter()
+        .filter_map(|token| {
+            let class = classify(&token.token_type)?;
+            Some(Highlight {
+                span: token.span,
+                class,
+            })
+        })
+        .collect()
+}
+
+/// Renders `source` with ANSI color escapes for terminals.
+pub fn to_ansi(source: &str) -> String {
+    render(
+        source,
+        |text| text.to_string(),
+        |class, text| match class.ansi_color() {
+            Some(color) => format!("{color}{text}\x1b[0m"),
+            None => text.to_string(),
+        },
+    )
+}
+
+/// Renders `source` as an HTML fragment with one `<span>` per classified piece.
+pub fn to_html(source: &str) -> String {
+    let body = render(source, escape_html, |class, text| {
+        format!(
+            "<span class=\"{}\">{}</span>",
+            class.name(),
+            escape_html(text)
+        )
+    });
+    format!("<pre class=\"lox\">{body}</pre>")
+}
+
+fn render(
+    source: &str,
+    plain: impl Fn(&str) -> String,
+    styled: impl Fn(HighlightClass, &str) -> String,
+) -> String {
+    let mut output = String::with_capacity(source.len());
+    let mut position = 0;
+    for Highlight { span, class } in highlight(source) {
+        output.push_str(&plain(&source[position..span.start]));
+        output.push_str(&styled(class, &source[span.start..span.end]));
+        position = span.end;
+    }
+    output.push_str(&plain(&source[position..]));
+    output
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight() {
+        let source = "var x = 1; // one\nprint \"x\";";
+        let classes: Vec<(&str, HighlightClass)> = highlight(source)
+            .iter()
+            .map(|h| (&source[h.span.start..h.span.end], h.class))
+            .collect();
+        assert_eq!(
+            vec![
+                ("var", HighlightClass::Keyword),
+                ("x", HighlightClass::Identifier),
+                ("=", HighlightClass::Operator),
+                ("1", HighlightClass::Number),
+                (";", HighlightClass::Punctuation),
+                ("// one", HighlightClass::Comment),
+                ("print", HighlightClass::Keyword),
+                ("\"x\"", HighlightClass::String),
+                (";", HighlightClass::Punctuation),
+            ],
+            classes
+        );
+    }
+
+    #[test]
+    fn test_to_html() {
+        assert_eq!(
+            "<pre class=\"lox\"><span class=\"identifier\">a</span> \
+             <span class=\"operator\">&lt;</span> <span class=\"string\">&quot;&amp;&quot;</span></pre>",
+            to_html("a < \"&\"")
+        );
+    }
+
+    #[test]
+    fn test_to_ansi_keeps_source() {
+        let source = "fun f() {\n  // nothing\n}\n";
+        let ansi = to_ansi(source);
+        let stripped = ansi
+            .replace("\x1b[0m", "")
+            .replace("\x1b[35m", "")
+            .replace("\x1b[90m", "");
+        assert_eq!(source, stripped);
+    }
+}
@@ -0,0 +1,20 @@
+//! A Lox implementation following *Crafting Interpreters*.
+//!
+//! The public API is re-exported from the crate root and follows semantic
+//! versioning. Types that are expected to grow are `#[non_exhaustive]`, so
+//! adding a token type or a field is not a breaking change.
+//!
+//! ```
+//! let tokens = lox_rs::scan("print 1 + 2;").unwrap();
+//! assert_eq!(lox_rs::TokenType::Print, tokens[0].token_type);
+//! ```
+
+pub mod diagnostics;
+pub mod doc;
+pub mod highlight;
+mod scanner;
+mod token;
+mod utils;
+
+pub use scanner::{scan, ScanError, Scanner, ScannerLimits, TokenTransform};
+pub use token::{FileId, Span, Token, TokenType};
@@ -1,45 +1,242 @@
-use crate::scanner::Scanner;
+use crate::allowlist::Allowlist;
+use lox_rs::diagnostics::{Diagnostic, ErrorCode};
+use lox_rs::{doc, highlight};
+use lox_rs::{FileId, ScanError, Scanner, ScannerLimits};
+use stats::RunStats;
 use std::error::Error;
 use std::fs;
-use std::io::Write;
-use std::sync::OnceLock;
+use std::io::{IsTerminal, Read, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 
-mod scanner;
-mod token;
-mod utils;
+mod allowlist;
+mod bench;
+mod conformance;
+mod console;
+mod crash;
+mod stats;
 
-static HAD_ERROR: OnceLock<bool> = OnceLock::new();
+static HAD_ERROR: AtomicBool = AtomicBool::new(false);
+
+const SUBCOMMANDS: [&str; 9] = [
+    "analyze",
+    "bench",
+    "check",
+    "doc",
+    "explain",
+    "highlight",
+    "run",
+    "stats",
+    "test-suite",
+];
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = std::env::args().collect();
-    match args.len() {
-        2 => Lox::default().run_file(&args[1])?,
-        3.. => {
-            println!("Usage lox-rs [script]");
-            std::process::exit(64);
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    // Everything after `--` is passed on to the script rather than parsed
+    let script_args = match args.iter().position(|arg| arg == "--") {
+        Some(index) => args.split_off(index).split_off(1),
+        None => Vec::new(),
+    };
+    let mut lox = Lox {
+        minimize: take_flag(&mut args, "--minimize"),
+        script_args,
+        stats_file: std::env::var_os(stats::STATS_FILE_VAR).map(PathBuf::from),
+        ..Default::default()
+    };
+
+    if let Some(allowlist_path) = take_option(&mut args, "--allowlist") {
+        lox.allowlist = Some(Allowlist::load(&allowlist_path)?);
+    }
+    if let Some(value) = take_option(&mut args, "--max-string-length") {
+        lox.limits.max_string_length = parse_limit(&value);
+    }
+    if let Some(value) = take_option(&mut args, "--max-identifier-length") {
+        lox.limits.max_identifier_length = parse_limit(&value);
+    }
+    if let Some(value) = take_option(&mut args, "--max-tokens") {
+        lox.limits.max_tokens = parse_limit(&value);
+    }
+    if let Some(value) = take_option(&mut args, "--max-errors") {
+        lox.limits.max_errors = parse_limit(&value);
+    }
+    if let Some(value) = take_option(&mut args, "--error-format") {
+        lox.error_format = match value.as_str() {
+            "human" => ErrorFormat::Human,
+            "json" => ErrorFormat::Json,
+            _ => usage(),
+        };
+    }
+
+    let color = take_option(&mut args, "--color").unwrap_or_else(|| "auto".to_string());
+    let color = console::use_color(&color).unwrap_or_else(|| usage());
+
+    match args.as_slice() {
+        [command, flag, options @ ..] if command == "bench" && flag == "--suite" => {
+            bench_suite(options.to_vec())?
+        }
+        [command, options @ ..] if command == "test-suite" && !options.is_empty() => {
+            test_suite(options.to_vec(), color)?
+        }
+        [command] if command == "check" => lox.check(".")?,
+        [command, path] if command == "check" => lox.check(path)?,
+        [command, file_paths @ ..] if command == "run" && !file_paths.is_empty() => {
+            lox.run_files(file_paths)?
+        }
+        [command] if command == "stats" => match &lox.stats_file {
+            Some(stats_file) => print!("{}", stats::summarize(stats_file)?),
+            None => println!(
+                "No stats file, set {} to record stats.",
+                stats::STATS_FILE_VAR
+            ),
+        },
+        [command, stats_file] if command == "stats" => {
+            print!("{}", stats::summarize(Path::new(stats_file))?);
+        }
+        [command, code] if command == "explain" => match ErrorCode::from_code(code) {
+            Some(error_code) => {
+                println!("{error_code}: {}\n", error_code.message());
+                println!("{}", error_code.explanation());
+            }
+            None => {
+                println!("Unknown error code '{code}'.");
+                std::process::exit(64);
+            }
+        },
+        [command, flag, file_path] if command == "analyze" && flag == "--classes" => {
+            let items = doc::declarations(&fs::read_to_string(file_path)?);
+            print!("{}", doc::class_hierarchy(&items));
         }
-        _ => Lox::default().run_prompt()?
+        [command, file_path] if command == "doc" => {
+            let items = doc::extract(&fs::read_to_string(file_path)?);
+            print!("{}", doc::to_markdown(file_path, &items));
+        }
+        [command, file_path] if command == "highlight" => {
+            let source = fs::read_to_string(file_path)?;
+            if color {
+                print!("{}", highlight::to_ansi(&source));
+            } else {
+                print!("{source}");
+            }
+        }
+        [command, flag, file_path] if command == "highlight" && flag == "--html" => {
+            println!("{}", highlight::to_html(&fs::read_to_string(file_path)?));
+        }
+        // A subcommand with missing or extra operands is not a script name
+        [command, ..] if SUBCOMMANDS.contains(&command.as_str()) => usage(),
+        [file_path] => lox.run_file(file_path)?,
+        // A program piped in is run as a script rather than line by line
+        [] if !std::io::stdin().is_terminal() => lox.run_stdin()?,
+        // Only vetted scripts may run when an allowlist is given
+        [] if lox.allowlist.is_none() => lox.run_prompt()?,
+        [] => {
+            println!("The REPL is disabled in allowlist mode, pass a script to run.");
+            std::process::exit(77);
+        }
+        _ => usage(),
     }
     Ok(())
 }
 
-pub fn error(line: usize, message: &str) -> Result<(), Box<dyn Error>> {
-    report(line, "", message)?;
+fn usage() -> ! {
+    println!("Usage lox-rs [--allowlist hashes.txt] [--max-string-length n] [--max-identifier-length n] [--max-tokens n] [--max-errors n] [--error-format human|json] [--minimize] [script] [-- args...]");
+    println!("      lox-rs run [options] [script...] [-- args...]");
+    println!("      lox-rs analyze --classes [script]");
+    println!("      lox-rs bench --suite [--json results.json] [--baseline results.json] [dir]");
+    println!("      lox-rs check [--error-format human|json] [path]");
+    println!("      lox-rs doc [script]");
+    println!("      lox-rs explain [error code]");
+    println!("      lox-rs highlight [--html] [--color auto|always|never] [script]");
+    println!("      lox-rs stats [stats file]");
+    println!("      lox-rs test-suite [--verbose] [--color auto|always|never] [craftinginterpreters test dir]");
+    std::process::exit(64);
+}
+
+/// Removes a flag from the arguments, returning whether it was given.
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    let index = args.iter().position(|arg| arg == name);
+    if let Some(index) = index {
+        args.remove(index);
+    }
+    index.is_some()
+}
+
+/// Removes an option and its value from the arguments, returning the value.
+///
+/// The value may be given as the next argument or joined with `=`.
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let joined = args.iter().position(|arg| {
+        arg.strip_prefix(name)
+            .is_some_and(|rest| rest.starts_with('='))
+    });
+    if let Some(index) = joined {
+        return Some(args.remove(index)[name.len() + 1..].to_string());
+    }
+
+    let index = args.iter().position(|arg| arg == name)?;
+    if index + 1 >= args.len() {
+        usage();
+    }
+    let value = args.remove(index + 1);
+    args.remove(index);
+    Some(value)
+}
+
+fn parse_limit(value: &str) -> usize {
+    value.parse().unwrap_or_else(|_| usage())
+}
+
+pub fn error(line: usize, code: ErrorCode, message: &str) -> Result<(), Box<dyn Error>> {
+    report(line, code, "", message)?;
     Ok(())
 }
 
-fn report(line: usize, location: &str, message: &str) -> Result<(), Box<dyn Error>> {
-    println!("[line {line}] Error{location}: {message}");
+fn report(
+    line: usize,
+    code: ErrorCode,
+    location: &str,
+    message: &str,
+) -> Result<(), Box<dyn Error>> {
+    println!("[line {line}] Error[{code}]{location}: {message}");
 
-    HAD_ERROR.set(false).unwrap();
+    HAD_ERROR.store(true, Ordering::Relaxed);
     Ok(())
 }
 
+/// How diagnostics are printed.
+#[derive(Clone, Copy, Default, PartialEq)]
+enum ErrorFormat {
+    /// `[line N] Error[L0001]: message` on stdout
+    #[default]
+    Human,
+    /// One JSON object per diagnostic on stderr, for editors and CI
+    Json,
+}
+
+/// Decodes a script, describing invalid UTF-8 as a diagnostic for the file.
+fn decode_source(file: &str, bytes: Vec<u8>) -> Result<String, Diagnostic> {
+    String::from_utf8(bytes)
+        .map_err(|error| Diagnostic::from_utf8_error(error.utf8_error(), file, error.as_bytes()))
+}
+
 #[derive(Default)]
-struct Lox {}
+struct Lox {
+    allowlist: Option<Allowlist>,
+    error_format: ErrorFormat,
+    limits: ScannerLimits,
+    minimize: bool,
+    /// Arguments after `--`, for the script to read through `args()`
+    #[allow(dead_code)] // Read once the interpreter provides natives
+    script_args: Vec<String>,
+    /// Where to record usage stats of script runs, opted into with `LOX_STATS_FILE`
+    stats_file: Option<PathBuf>,
+}
 
 impl Lox {
     fn run_prompt(&mut self) -> Result<(), Box<dyn Error>> {
+        // Everything entered so far, for commands that look at the whole session
+        let mut lines: Vec<String> = Vec::new();
         loop {
             print!("> ");
             std::io::stdout().flush()?;
@@ -50,9 +247,15 @@ impl Lox {
                     if input.trim().is_empty() {
                         break;
                     }
-                    self.run(&input)?;
+                    if input.trim() == ":classes" {
+                        let items = doc::declarations(&lines.concat());
+                        print!("{}", doc::class_hierarchy(&items));
+                        continue;
+                    }
+                    self.run("<repl>", &input)?;
+                    lines.push(input);
 
-                    HAD_ERROR.set(false).unwrap();
+                    HAD_ERROR.store(false, Ordering::Relaxed);
                 }
                 Err(error) => println!("{error}"),
             }
@@ -61,22 +264,308 @@ impl Lox {
     }
 
     fn run_file(&self, file_path: &str) -> Result<(), Box<dyn Error>> {
-        let source = fs::read_to_string(file_path)?;
-        self.run(&source)?;
+        match fs::read(file_path) {
+            Ok(bytes) => self.run_script(file_path, bytes),
+            Err(error) => {
+                self.report_diagnostic(&Diagnostic::unreadable(&error, file_path));
+                std::process::exit(65);
+            }
+        }
+    }
+
+    fn run_stdin(&self) -> Result<(), Box<dyn Error>> {
+        let mut bytes = Vec::new();
+        std::io::stdin().read_to_end(&mut bytes)?;
+        self.run_script("<stdin>", bytes)
+    }
+
+    fn run_script(&self, name: &str, bytes: Vec<u8>) -> Result<(), Box<dyn Error>> {
+        self.check_allowlist(name, &bytes);
+        let source = decode_source(name, bytes).unwrap_or_else(|diagnostic| {
+            self.report_diagnostic(&diagnostic);
+            std::process::exit(65);
+        });
+        let run_stats = self.run(name, &source)?;
+        if let Some(stats_file) = &self.stats_file {
+            if let Err(error) = stats::record(stats_file, name, &run_stats) {
+                println!("Could not record stats: {error}");
+            }
+        }
 
-        if HAD_ERROR.get().is_some_and(|e| *e) {
+        if HAD_ERROR.load(Ordering::Relaxed) {
             std::process::exit(65);
         }
         Ok(())
     }
 
-    fn run(&self, source: &str) -> Result<(), Box<dyn Error>> {
-        let mut scanner = Scanner::new(source);
-        let tokens = scanner.scan_tokens();
+    fn check_allowlist(&self, name: &str, bytes: &[u8]) {
+        if let Some(allowlist) = &self.allowlist {
+            if !allowlist.permits(bytes) {
+                println!("Script '{name}' is not in the allowlist.");
+                std::process::exit(77);
+            }
+        }
+    }
 
-        for token in tokens {
+    fn run(&self, file: &str, source: &str) -> Result<RunStats, Box<dyn Error>> {
+        let (scanner, scan_time) = self.scan(FileId::default(), source);
+        for token in scanner.tokens() {
             println!("{token}");
         }
+
+        for scan_error in scanner.errors() {
+            match self.error_format {
+                ErrorFormat::Human => error(scan_error.line, scan_error.code, &scan_error.message)?,
+                ErrorFormat::Json => {
+                    self.report_diagnostic(&Diagnostic::from_scan_error(scan_error, file, source))
+                }
+            }
+        }
+        Ok(RunStats {
+            phases: vec![("scanning".to_string(), scan_time)],
+            errors: scanner.errors().len(),
+        })
+    }
+
+    /// Runs several files as one program. Every file is scanned first, and nothing
+    /// runs unless all of them are free of errors.
+    fn run_files(&self, file_paths: &[String]) -> Result<(), Box<dyn Error>> {
+        let mut sources = Vec::new();
+        let mut run_stats = RunStats::default();
+        for (index, file_path) in file_paths.iter().enumerate() {
+            let bytes = match fs::read(file_path) {
+                Ok(bytes) => bytes,
+                Err(error) => {
+                    self.report_diagnostic(&Diagnostic::unreadable(&error, file_path));
+                    run_stats.errors += 1;
+                    continue;
+                }
+            };
+            self.check_allowlist(file_path, &bytes);
+            match decode_source(file_path, bytes) {
+                Ok(source) => sources.push((FileId(index), source)),
+                Err(diagnostic) => {
+                    self.report_diagnostic(&diagnostic);
+                    run_stats.errors += 1;
+                }
+            }
+        }
+
+        let mut scanners = Vec::new();
+        let mut scan_time = Duration::ZERO;
+        for (file, source) in &sources {
+            let (scanner, file_scan_time) = self.scan(*file, source);
+            for scan_error in scanner.errors() {
+                // Errors carry the file they were found in
+                self.report_scan_error(&file_paths[scan_error.file.0], source, scan_error);
+            }
+            scan_time += file_scan_time;
+            run_stats.errors += scanner.errors().len();
+            scanners.push(scanner);
+        }
+        // The files form one program, so they are recorded as a single run
+        run_stats.phases.push(("scanning".to_string(), scan_time));
+        if let Some(stats_file) = &self.stats_file {
+            if let Err(error) = stats::record(stats_file, &file_paths.join(" "), &run_stats) {
+                println!("Could not record stats: {error}");
+            }
+        }
+
+        if HAD_ERROR.load(Ordering::Relaxed) {
+            std::process::exit(65);
+        }
+        for scanner in &scanners {
+            for token in scanner.tokens() {
+                println!("{token}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Scans `source`, turning a panic in the scanner into a crash report.
+    fn scan<'a>(&self, file: FileId, source: &'a str) -> (Scanner<'a>, Duration) {
+        let mut scanner = Scanner::new(source)
+            .with_limits(self.limits)
+            .with_file(file);
+        let scanned = panic::catch_unwind(AssertUnwindSafe(|| {
+            let started = Instant::now();
+            scanner.scan_tokens();
+            started.elapsed()
+        }));
+        let scan_time = scanned.unwrap_or_else(|payload| {
+            crash::report("scanning", source, scanner.line(), payload.as_ref());
+            if self.minimize {
+                crash::report_minimized(source, |candidate| {
+                    Scanner::new(candidate)
+                        .with_limits(self.limits)
+                        .scan_tokens();
+                });
+            } else {
+                eprintln!("Run again with --minimize to reduce the input to a small reproducer.");
+            }
+            std::process::exit(70);
+        });
+        (scanner, scan_time)
+    }
+
+    /// Reports an error of one of several files, naming the file it was found in.
+    fn report_scan_error(&self, file_path: &str, source: &str, scan_error: &ScanError) {
+        match self.error_format {
+            ErrorFormat::Human => {
+                println!("{file_path}: {scan_error}");
+                HAD_ERROR.store(true, Ordering::Relaxed);
+            }
+            ErrorFormat::Json => {
+                self.report_diagnostic(&Diagnostic::from_scan_error(scan_error, file_path, source))
+            }
+        }
+    }
+
+    /// Reports a diagnostic that is not tied to the scanner, such as an unreadable file.
+    fn report_diagnostic(&self, diagnostic: &Diagnostic) {
+        match self.error_format {
+            ErrorFormat::Human => println!("{}: {diagnostic}", diagnostic.file),
+            ErrorFormat::Json => eprintln!("{}", diagnostic.to_json()),
+        }
+
+        HAD_ERROR.store(true, Ordering::Relaxed);
+    }
+
+    /// Reports the diagnostics of every `.lox` file under `path` without running them.
+    fn check(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let mut file_paths = Vec::new();
+        collect_lox_files(Path::new(path), &mut file_paths)?;
+
+        let mut error_count = 0;
+        let mut failed_files = 0;
+        for file_path in &file_paths {
+            let name = file_path.display().to_string();
+            let source = fs::read(file_path)
+                .map_err(|error| Diagnostic::unreadable(&error, &name))
+                .and_then(|bytes| decode_source(&name, bytes));
+            let source = match source {
+                Ok(source) => source,
+                Err(diagnostic) => {
+                    // Keep checking the other files
+                    self.report_diagnostic(&diagnostic);
+                    error_count += 1;
+                    failed_files += 1;
+                    continue;
+                }
+            };
+            let (scanner, _) = self.scan(FileId::default(), &source);
+
+            for scan_error in scanner.errors() {
+                self.report_scan_error(&name, &source, scan_error);
+            }
+            if !scanner.errors().is_empty() {
+                error_count += scanner.errors().len();
+                failed_files += 1;
+            }
+        }
+
+        println!(
+            "Checked {} file(s): {error_count} error(s) in {failed_files} file(s).",
+            file_paths.len()
+        );
+        if error_count > 0 {
+            std::process::exit(65);
+        }
         Ok(())
     }
 }
+
+/// Times the benchmark programs, optionally saving the results and comparing them to a
+/// baseline saved earlier. Exits with 1 if a program got slower than the baseline.
+fn bench_suite(mut args: Vec<String>) -> Result<(), Box<dyn Error>> {
+    let json_path = take_option(&mut args, "--json");
+    let baseline_path = take_option(&mut args, "--baseline");
+    let dir = match args.as_slice() {
+        [] => bench::SUITE_DIR,
+        [dir] => dir.as_str(),
+        _ => usage(),
+    };
+
+    let mut file_paths = Vec::new();
+    collect_lox_files(Path::new(dir), &mut file_paths)?;
+    let results = bench::run_suite(&file_paths)?;
+    if let Some(json_path) = json_path {
+        fs::write(json_path, bench::to_json(&results))?;
+    }
+
+    match baseline_path {
+        Some(baseline_path) => {
+            let baseline = bench::parse_json(&fs::read_to_string(baseline_path)?);
+            let (report, regressed) = bench::compare(&results, &baseline);
+            print!("{report}");
+            if regressed {
+                std::process::exit(1);
+            }
+        }
+        None => {
+            for result in &results {
+                println!(
+                    "{:<20} {:<10} {:>12.3?}",
+                    result.name, result.phase, result.time
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs the craftinginterpreters test corpus in `dir` and prints how many tests pass per
+/// chapter. Exits with 1 if any test failed.
+fn test_suite(mut args: Vec<String>, color: bool) -> Result<(), Box<dyn Error>> {
+    let verbose = take_flag(&mut args, "--verbose");
+    let [dir] = args.as_slice() else {
+        usage();
+    };
+
+    let mut file_paths = Vec::new();
+    collect_lox_files(Path::new(dir), &mut file_paths)?;
+    let results = conformance::run_suite(Path::new(dir), &file_paths)?;
+    if verbose {
+        for result in &results {
+            match &result.failure {
+                Some(conformance::Failure::Error(message)) => {
+                    println!("FAIL {}: {message}", result.path.display())
+                }
+                Some(conformance::Failure::Output { expected, actual }) => {
+                    println!("FAIL {}: output differs", result.path.display());
+                    print!("{}", conformance::render_diff(expected, actual, color));
+                }
+                None => {}
+            }
+        }
+        println!();
+    }
+
+    print!("{}", conformance::summarize(&results));
+    if results.iter().any(|result| result.failure.is_some()) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Collects `path` itself if it is a file, or all `.lox` files below it, in a stable order.
+fn collect_lox_files(path: &Path, file_paths: &mut Vec<PathBuf>) -> Result<(), Box<dyn Error>> {
+    if !path.is_dir() {
+        file_paths.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut entries = fs::read_dir(path)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<PathBuf>, _>>()?;
+    entries.sort();
+
+    for entry in entries {
+        if entry.is_dir() {
+            collect_lox_files(&entry, file_paths)?;
+        } else if entry.extension().is_some_and(|ext| ext == "lox") {
+            file_paths.push(entry);
+        }
+    }
+    Ok(())
+}
@@ -1,6 +1,9 @@
 use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
 use std::sync::OnceLock;
-use crate::token::{Token, TokenType};
+use crate::diagnostics::ErrorCode;
+use crate::token::{FileId, Span, Token, TokenType};
 use crate::utils;
 
 static KEYWORDS: OnceLock<HashMap<&str, TokenType>> = OnceLock::new();
@@ -9,19 +12,32 @@ fn get_keyword_token(literal: &str) -> Option<&TokenType> {
     let keywords = KEYWORDS.get_or_init(|| {
         let mut map = HashMap::new();
         map.insert("and", TokenType::And);
+        map.insert("break", TokenType::Break);
+        map.insert("case", TokenType::Case);
+        map.insert("catch", TokenType::Catch);
         map.insert("class", TokenType::Class);
+        map.insert("const", TokenType::Const);
+        map.insert("continue", TokenType::Continue);
+        map.insert("default", TokenType::Default);
         map.insert("else", TokenType::Else);
         map.insert("false", TokenType::False);
+        map.insert("finally", TokenType::Finally);
         map.insert("for", TokenType::For);
         map.insert("fun", TokenType::Fun);
         map.insert("if", TokenType::If);
+        map.insert("import", TokenType::Import);
+        map.insert("in", TokenType::In);
+        map.insert("macro", TokenType::Macro);
         map.insert("nil", TokenType::Nil);
         map.insert("or", TokenType::Or);
         map.insert("print", TokenType::Print);
         map.insert("return", TokenType::Return);
         map.insert("super", TokenType::Super);
+        map.insert("switch", TokenType::Switch);
         map.insert("this", TokenType::This);
+        map.insert("throw", TokenType::Throw);
         map.insert("true", TokenType::True);
+        map.insert("try", TokenType::Try);
         map.insert("var", TokenType::Var);
         map.insert("while", TokenType::While);
         map
@@ -29,12 +45,81 @@ fn get_keyword_token(literal: &str) -> Option<&TokenType> {
     keywords.get(literal)
 }
 
+/// Upper bounds on what the scanner accepts, so that extreme or untrusted input
+/// produces diagnostics instead of unbounded memory growth.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct ScannerLimits {
+    /// Maximum length of a string literal in bytes, excluding the quotes
+    pub max_string_length: usize,
+    /// Maximum length of an identifier in bytes
+    pub max_identifier_length: usize,
+    /// Maximum number of tokens, excluding the final EOF token
+    pub max_tokens: usize,
+    /// Maximum number of errors, including the one reporting that the limit was reached
+    pub max_errors: usize,
+}
+
+impl Default for ScannerLimits {
+    fn default() -> Self {
+        Self {
+            max_string_length: 1024 * 1024,
+            max_identifier_length: 1024,
+            max_tokens: 1_000_000,
+            max_errors: 1000,
+        }
+    }
+}
+
+/// An error found while scanning, reported by the caller once scanning is done.
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub struct ScanError {
+    pub file: FileId,
+    pub line: usize,
+    pub code: ErrorCode,
+    pub message: String,
+    /// The offending part of the source
+    pub span: Span,
+}
+
+impl Display for ScanError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[line {}] Error[{}]: {}", self.line, self.code, self.message)
+    }
+}
+
+impl Error for ScanError {}
+
+/// Scans `source` with the default limits, returning its tokens or all errors found.
+pub fn scan(source: &str) -> Result<Vec<Token>, Vec<ScanError>> {
+    let mut scanner = Scanner::new(source);
+    scanner.scan_tokens();
+    if scanner.errors.is_empty() {
+        Ok(scanner.tokens)
+    } else {
+        Err(scanner.errors)
+    }
+}
+
+/// Rewrites the scanned tokens before they are parsed, for experiments such as new
+/// keywords or instrumentation without changing the scanner itself.
+pub type TokenTransform = Box<dyn Fn(Vec<Token>) -> Vec<Token>>;
+
+/// Turns source text into tokens, collecting errors instead of stopping at the first one.
 pub struct Scanner<'a> {
     source: &'a str,
     start: usize,
     current: usize,
     line: usize,
+    /// The line `start` is on, where errors are reported
+    start_line: usize,
     tokens: Vec<Token>,
+    errors: Vec<ScanError>,
+    limits: ScannerLimits,
+    trivia: bool,
+    file: FileId,
+    transforms: Vec<TokenTransform>,
 }
 
 impl<'a> Scanner<'a> {
@@ -44,20 +129,113 @@ impl<'a> Scanner<'a> {
             start: 0,
             current: 0,
             line: 1,
+            start_line: 1,
             tokens: Vec::new(),
+            errors: Vec::new(),
+            limits: ScannerLimits::default(),
+            trivia: false,
+            file: FileId::default(),
+            transforms: Vec::new(),
         }
     }
 
+    pub fn with_limits(mut self, limits: ScannerLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Emits comment, whitespace and newline tokens instead of skipping them, so
+    /// that the source can be reconstructed exactly from the tokens. Text that
+    /// produces an error is kept as a [`TokenType::Invalid`] token.
+    pub fn with_trivia(mut self, trivia: bool) -> Self {
+        self.trivia = trivia;
+        self
+    }
+
+    /// Tags the tokens and errors with `file`, for programs made of several files.
+    pub fn with_file(mut self, file: FileId) -> Self {
+        self.file = file;
+        self
+    }
+
+    /// Registers a transformer that runs on the tokens once the whole source is scanned.
+    /// Transformers run in the order they are registered, and must keep the final
+    /// [`TokenType::Eof`] token.
+    pub fn with_transform(
+        mut self,
+        transform: impl Fn(Vec<Token>) -> Vec<Token> + 'static,
+    ) -> Self {
+        self.transforms.push(Box::new(transform));
+        self
+    }
+
+    /// Scans the whole source. The last token is always [`TokenType::Eof`].
     pub fn scan_tokens(&mut self) -> &Vec<Token> {
         while !self.is_at_end() {
             // We are at the beginning of the next lexeme
             self.start = self.current;
+            self.start_line = self.line;
             self.scan_token();
         }
-        self.tokens.push(Token::new(TokenType::Eof, "", self.line));
+        let span = Span {
+            start: self.current,
+            end: self.current,
+        };
+        self.tokens
+            .push(Token::new(TokenType::Eof, "", self.file, self.line, span));
+        for transform in &self.transforms {
+            self.tokens = transform(std::mem::take(&mut self.tokens));
+        }
         &self.tokens
     }
 
+    /// The line the scanner has reached
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The tokens scanned so far
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    pub fn errors(&self) -> &[ScanError] {
+        &self.errors
+    }
+
+    fn error(&mut self, mut code: ErrorCode) {
+        if self.errors.len() + 1 >= self.limits.max_errors {
+            // Stop scanning, the rest of the input would only add more errors
+            self.current = self.source.len();
+            code = ErrorCode::TooManyErrors;
+        }
+        if self.trivia {
+            // Bypasses the token limit, which may be the reason for the error
+            let text = &self.source[self.start..self.current];
+            let span = Span {
+                start: self.start,
+                end: self.current,
+            };
+            self.tokens.push(Token::new(
+                TokenType::Invalid,
+                text,
+                self.file,
+                self.line,
+                span,
+            ));
+        }
+        self.errors.push(ScanError {
+            file: self.file,
+            line: self.start_line,
+            code,
+            message: code.message().to_string(),
+            span: Span {
+                start: self.start,
+                end: self.current,
+            },
+        });
+    }
+
     fn scan_token(&mut self) {
         let char = self.advance();
         match char {
@@ -65,6 +243,8 @@ impl<'a> Scanner<'a> {
             ')' => self.add_token(TokenType::RightParen),
             '{' => self.add_token(TokenType::LeftBrace),
             '}' => self.add_token(TokenType::RightBrace),
+            '[' => self.add_token(TokenType::LeftBracket),
+            ']' => self.add_token(TokenType::RightBracket),
             ',' => self.add_token(TokenType::Comma),
             '.' => self.add_token(TokenType::Dot),
             '-' => self.add_token(TokenType::Minus),
@@ -105,21 +285,37 @@ impl<'a> Scanner<'a> {
                     while self.peek() != '\n' && !self.is_at_end() {
                         self.advance();
                     }
+                    if self.trivia {
+                        self.add_token(TokenType::Comment);
+                    }
                 } else {
                     self.add_token(TokenType::Slash);
                 }
             }
-            ' ' | '\r' | '\t' => {} // Ignore whitespace
-            '\n' => self.line += 1,
+            ' ' | '\r' | '\t' => {
+                // Ignore whitespace unless trivia is requested
+                if self.trivia {
+                    while matches!(self.peek(), ' ' | '\r' | '\t') {
+                        self.advance();
+                    }
+                    self.add_token(TokenType::Whitespace);
+                }
+            }
+            '\n' => {
+                if self.trivia {
+                    self.add_token(TokenType::Newline);
+                }
+                self.line += 1;
+            }
             '"' => self.scan_string_literal(),
             _ => {
-                if char.is_numeric() {
+                if char.is_ascii_digit() {
                     self.scan_number_literal();
                 } else if utils::is_alpha(char) {
                     self.scan_identifier();
                 }
                 else {
-                    super::error(self.line, "Unexpected character.").unwrap()
+                    self.error(ErrorCode::UnexpectedCharacter)
                 };
             }
         }
@@ -131,6 +327,11 @@ impl<'a> Scanner<'a> {
         }
 
         let value = &self.source[self.start..self.current];
+        if value.len() > self.limits.max_identifier_length {
+            self.error(ErrorCode::IdentifierTooLong);
+            return;
+        }
+
         match get_keyword_token(value) {
             None => self.add_token(TokenType::Identifier(value.to_string())),
             Some(token_type) => self.add_token(token_type.clone())
@@ -138,16 +339,16 @@ impl<'a> Scanner<'a> {
     }
 
     fn scan_number_literal(&mut self) {
-        while self.peek().is_numeric() {
+        while self.peek().is_ascii_digit() {
             self.advance();
         }
 
         // Look for fractional part
-        if self.peek() == '.' && self.peek_next().is_numeric() {
+        if self.peek() == '.' && self.peek_next().is_ascii_digit() {
             // Consume the "."
             self.advance();
 
-            while self.peek().is_numeric() {
+            while self.peek().is_ascii_digit() {
                 self.advance();
             }
         }
@@ -166,7 +367,7 @@ impl<'a> Scanner<'a> {
         }
 
         if self.is_at_end() {
-            super::error(self.line, "Unterminated string.").unwrap();
+            self.error(ErrorCode::UnterminatedString);
             return;
         }
 
@@ -175,53 +376,45 @@ impl<'a> Scanner<'a> {
 
         // Trim the surrounding quotes
         let value = &self.source[self.start + 1..self.current - 1];
+        if value.len() > self.limits.max_string_length {
+            self.error(ErrorCode::StringTooLong);
+            return;
+        }
         self.add_token(TokenType::String(value.to_string()));
     }
 
     fn add_token(&mut self, token_type: TokenType) {
+        if self.tokens.len() >= self.limits.max_tokens {
+            // Stop scanning, the rest of the input would only report the same error
+            self.current = self.source.len();
+            self.error(ErrorCode::TooManyTokens);
+            return;
+        }
+
         let text = &self.source[self.start..self.current];
-        self.tokens.push(Token::new(token_type, text, self.line));
+        let span = Span {
+            start: self.start,
+            end: self.current,
+        };
+        self.tokens
+            .push(Token::new(token_type, text, self.file, self.line, span));
     }
 
     fn advance(&mut self) -> char {
-        let chars = self.source[self.current..self.current + 1]
-            .chars()
-            .collect::<Vec<char>>();
-
-        self.current += 1;
-        match chars.first() {
-            None => '\0',
-            Some(value) => *value,
-        }
+        // `current` is a byte offset, so step over the whole UTF-8 sequence
+        let char = self.peek();
+        self.current += char.len_utf8();
+        char
     }
 
     fn peek(&self) -> char {
-        if self.is_at_end() {
-            return '\0';
-        }
-        let chars = self.source[self.current..self.current + 1]
-            .chars()
-            .collect::<Vec<char>>();
-
-        match chars.first() {
-            None => '\0',
-            Some(value) => *value,
-        }
+        self.source[self.current..].chars().next().unwrap_or('\0')
     }
 
     fn peek_next(&self) -> char {
-        if self.current + 1 >= self.source.len() {
-            return '\0';
-        }
-
-        let chars = self.source[self.current + 1..self.current + 2]
-            .chars()
-            .collect::<Vec<char>>();
-
-        match chars.first() {
-            None => '\0',
-            Some(value) => *value,
-        }
+        let mut chars = self.source[self.current..].chars();
+        chars.next();
+        chars.next().unwrap_or('\0')
     }
 
     fn is_at_end(&self) -> bool {
@@ -229,24 +422,17 @@ impl<'a> Scanner<'a> {
     }
 
     fn matches(&mut self, expected: char) -> bool {
-        if self.is_at_end() {
+        if self.is_at_end() || self.peek() != expected {
             return false;
         }
 
-        let chars = self.source[self.current..self.current + 1]
-            .chars()
-            .collect::<Vec<char>>();
-
-        if chars.first().is_some_and(|c| *c != expected) {
-            return false;
-        }
-
-        self.current += 1;
+        self.current += expected.len_utf8();
         true
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::get_first)]
 mod tests {
     use super::*;
 
@@ -321,4 +507,448 @@ mod tests {
             assert_eq!(TokenType::Class, t.token_type);
         }
     }
+
+    fn token_types(source: &str) -> Vec<TokenType> {
+        let mut scanner = Scanner::new(source);
+        scanner
+            .scan_tokens()
+            .iter()
+            .map(|t| t.token_type.clone())
+            .collect()
+    }
+
+    /// Keywords reserved ahead of the statements that use them.
+    #[test]
+    fn test_scan_reserved_keywords() {
+        let cases = [
+            ("break continue", vec![TokenType::Break, TokenType::Continue]),
+            (
+                "const constant",
+                vec![
+                    TokenType::Const,
+                    TokenType::Identifier("constant".to_string()),
+                ],
+            ),
+            (
+                "switch case default",
+                vec![TokenType::Switch, TokenType::Case, TokenType::Default],
+            ),
+            (
+                "throw try catch finally",
+                vec![
+                    TokenType::Throw,
+                    TokenType::Try,
+                    TokenType::Catch,
+                    TokenType::Finally,
+                ],
+            ),
+            ("import", vec![TokenType::Import]),
+            ("macro", vec![TokenType::Macro]),
+            (
+                "in index",
+                vec![TokenType::In, TokenType::Identifier("index".to_string())],
+            ),
+        ];
+        for (source, mut expected) in cases {
+            expected.push(TokenType::Eof);
+            assert_eq!(expected, token_types(source), "{source}");
+        }
+    }
+
+    #[test]
+    fn test_scan_brackets() {
+        assert_eq!(
+            vec![
+                TokenType::Identifier("xs".to_string()),
+                TokenType::LeftBracket,
+                TokenType::Number(0.0),
+                TokenType::RightBracket,
+                TokenType::Eof
+            ],
+            token_types("xs[0]")
+        );
+    }
+
+    #[test]
+    fn test_crlf_line_numbers() {
+        let mut scanner = Scanner::new("var a;\r\n\"multi\r\nline\";\r\nb");
+        let tokens = scanner.scan_tokens();
+        let lines: Vec<usize> = tokens.iter().map(|t| t.line).collect();
+        assert_eq!(vec![1, 1, 1, 3, 3, 4, 4], lines);
+    }
+
+    #[test]
+    fn test_string_length_limit() {
+        let limits = ScannerLimits {
+            max_string_length: 3,
+            ..Default::default()
+        };
+        let mut scanner = Scanner::new("\"abc\" \"abcd\"").with_limits(limits);
+        let tokens = scanner.scan_tokens();
+        let token_types: Vec<&TokenType> = tokens.iter().map(|t| &t.token_type).collect();
+        assert_eq!(
+            vec![&TokenType::String("abc".to_string()), &TokenType::Eof],
+            token_types
+        );
+    }
+
+    #[test]
+    fn test_identifier_length_limit() {
+        let limits = ScannerLimits {
+            max_identifier_length: 3,
+            ..Default::default()
+        };
+        let mut scanner = Scanner::new("abc abcd").with_limits(limits);
+        let tokens = scanner.scan_tokens();
+        let token_types: Vec<&TokenType> = tokens.iter().map(|t| &t.token_type).collect();
+        assert_eq!(
+            vec![&TokenType::Identifier("abc".to_string()), &TokenType::Eof],
+            token_types
+        );
+    }
+
+    #[test]
+    fn test_token_limit() {
+        let limits = ScannerLimits {
+            max_tokens: 2,
+            ..Default::default()
+        };
+        let mut scanner = Scanner::new("1 + 2 + 3").with_limits(limits);
+        let tokens = scanner.scan_tokens();
+        assert_eq!(
+            3,
+            tokens.len(),
+            "there should be two tokens and one EOF token"
+        );
+        assert_eq!(TokenType::Eof, tokens[2].token_type);
+    }
+
+    #[test]
+    fn test_error_limit() {
+        let limits = ScannerLimits {
+            max_errors: 5,
+            ..Default::default()
+        };
+        let source = "@".repeat(10_000);
+        let mut scanner = Scanner::new(&source).with_limits(limits).with_trivia(true);
+        let tokens = scanner.scan_tokens();
+        assert_eq!(6, tokens.len(), "there should be five invalid and one EOF token");
+        let lexemes: String = tokens.iter().map(|t| t.lexeme.as_str()).collect();
+        assert_eq!(source, lexemes);
+
+        let codes: Vec<ErrorCode> = scanner.errors().iter().map(|e| e.code).collect();
+        assert_eq!(
+            vec![
+                ErrorCode::UnexpectedCharacter,
+                ErrorCode::UnexpectedCharacter,
+                ErrorCode::UnexpectedCharacter,
+                ErrorCode::UnexpectedCharacter,
+                ErrorCode::TooManyErrors
+            ],
+            codes
+        );
+    }
+
+    #[test]
+    fn test_scan_errors() {
+        let mut scanner = Scanner::new("a @\n\"open");
+        scanner.scan_tokens();
+        assert_eq!(
+            &[
+                ScanError {
+                    file: FileId::default(),
+                    line: 1,
+                    code: ErrorCode::UnexpectedCharacter,
+                    message: "Unexpected character.".to_string(),
+                    span: Span { start: 2, end: 3 }
+                },
+                ScanError {
+                    file: FileId::default(),
+                    line: 2,
+                    code: ErrorCode::UnterminatedString,
+                    message: "Unterminated string.".to_string(),
+                    span: Span { start: 4, end: 9 }
+                }
+            ],
+            scanner.errors()
+        );
+    }
+
+    #[test]
+    fn test_error_line_is_start_of_lexeme() {
+        let mut scanner = Scanner::new("var a = \"open\nmore\nlines");
+        scanner.scan_tokens();
+        assert_eq!(1, scanner.errors()[0].line);
+    }
+
+    #[test]
+    fn test_file_id() {
+        let mut scanner = Scanner::new("a @").with_file(FileId(2));
+        scanner.scan_tokens();
+        assert!(scanner.tokens().iter().all(|token| token.file == FileId(2)));
+        assert_eq!(FileId(2), scanner.errors()[0].file);
+    }
+
+    #[test]
+    fn test_transform() {
+        let mut scanner = Scanner::new("def f() {}")
+            .with_transform(|tokens| {
+                tokens
+                    .into_iter()
+                    .map(|mut token| {
+                        if token.token_type == TokenType::Identifier("def".to_string()) {
+                            token.token_type = TokenType::Fun;
+                        }
+                        token
+                    })
+                    .collect()
+            })
+            .with_transform(|tokens| {
+                tokens
+                    .into_iter()
+                    .filter(|token| token.token_type != TokenType::LeftBrace)
+                    .collect()
+            });
+        let tokens = scanner.scan_tokens();
+        let token_types: Vec<&TokenType> = tokens.iter().map(|t| &t.token_type).collect();
+        assert_eq!(
+            vec![
+                &TokenType::Fun,
+                &TokenType::Identifier("f".to_string()),
+                &TokenType::LeftParen,
+                &TokenType::RightParen,
+                &TokenType::RightBrace,
+                &TokenType::Eof
+            ],
+            token_types
+        );
+    }
+
+    /// Inputs that used to panic the scanner and end-of-input edge cases, kept as regression
+    /// tests for the fuzz target.
+    #[test]
+    fn test_fuzz_crashers() {
+        let crashers = [
+            "é", "\"é\"", "a€", "1é", "1.é", "!é", "/é", "٣", "1.٣", "\"", "/", "1.",
+        ];
+        for crasher in crashers {
+            let mut scanner = Scanner::new(crasher);
+            let tokens = scanner.scan_tokens();
+            assert_eq!(Some(&TokenType::Eof), tokens.last().map(|t| &t.token_type));
+        }
+    }
+
+    #[test]
+    fn test_scan_multibyte() {
+        assert_eq!(
+            vec![
+                TokenType::String("héllo".to_string()),
+                TokenType::Identifier("naïve".to_string()),
+                TokenType::Eof
+            ],
+            token_types("\"héllo\" naïve")
+        );
+    }
+
+    mod round_trip {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// Generates a token type together with a lexeme that scans back to it.
+        fn token() -> impl Strategy<Value = (TokenType, String)> {
+            let mut fixed = vec![
+                TokenType::LeftParen,
+                TokenType::RightParen,
+                TokenType::LeftBrace,
+                TokenType::RightBrace,
+                TokenType::LeftBracket,
+                TokenType::RightBracket,
+                TokenType::Comma,
+                TokenType::Dot,
+                TokenType::Minus,
+                TokenType::Plus,
+                TokenType::Semicolon,
+                TokenType::Slash,
+                TokenType::Star,
+                TokenType::Bang,
+                TokenType::BangEqual,
+                TokenType::Equal,
+                TokenType::EqualEqual,
+                TokenType::Greater,
+                TokenType::GreaterEqual,
+                TokenType::Less,
+                TokenType::LessEqual,
+            ];
+            // Every keyword, sorted so that the cases do not depend on the map's order
+            get_keyword_token("");
+            let mut keywords: Vec<TokenType> = KEYWORDS.get().unwrap().values().cloned().collect();
+            keywords.sort_by_key(|token_type| token_type.to_string());
+            fixed.extend(keywords);
+
+            let fixed = prop::sample::select(fixed).prop_map(|token_type| {
+                let lexeme = token_type.to_string();
+                (token_type, lexeme)
+            });
+            let identifier =
+                "[a-zA-Z_\u{e0}-\u{f6}\u{f8}-\u{ff}][a-zA-Z0-9_\u{e0}-\u{f6}\u{f8}-\u{ff}]{0,10}"
+                    .prop_filter("keywords are not identifiers", |value| {
+                        get_keyword_token(value).is_none()
+                    })
+                    .prop_map(|value| (TokenType::Identifier(value.clone()), value));
+            let string = "[^\"]{0,20}"
+                .prop_map(|value| (TokenType::String(value.clone()), format!("\"{value}\"")));
+            let number = "[0-9]{1,8}(\\.[0-9]{1,8})?"
+                .prop_map(|value| (TokenType::Number(value.parse().unwrap()), value));
+
+            prop_oneof![fixed, identifier, string, number]
+        }
+
+        proptest! {
+            #[test]
+            fn test_rescan_printed_tokens(tokens in prop::collection::vec(token(), 0..50)) {
+                let source = tokens
+                    .iter()
+                    .map(|(_, lexeme)| lexeme.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(" ");
+                let mut scanner = Scanner::new(&source);
+                let scanned: Vec<TokenType> = scanner
+                    .scan_tokens()
+                    .iter()
+                    .map(|t| t.token_type.clone())
+                    .collect();
+
+                let mut expected: Vec<TokenType> = tokens.into_iter().map(|(t, _)| t).collect();
+                expected.push(TokenType::Eof);
+                prop_assert_eq!(expected, scanned);
+                prop_assert!(scanner.errors().is_empty());
+            }
+
+            #[test]
+            fn test_trivia_reconstructs_source(
+                tokens in prop::collection::vec(token(), 0..50),
+                separators in prop::collection::vec("[ \t\r\n]{1,3}|[ ]?//[^\n]*\n", 50)
+            ) {
+                let source: String = tokens
+                    .iter()
+                    .zip(&separators)
+                    .flat_map(|((_, lexeme), separator)| [lexeme.as_str(), separator.as_str()])
+                    .collect();
+                let mut scanner = Scanner::new(&source).with_trivia(true);
+                let lexemes: String = scanner
+                    .scan_tokens()
+                    .iter()
+                    .map(|t| t.lexeme.as_str())
+                    .collect();
+                prop_assert_eq!(source, lexemes);
+            }
+
+            #[test]
+            fn test_trivia_reconstructs_invalid_source(
+                tokens in prop::collection::vec(token(), 0..50),
+                invalid in prop::collection::vec("[@#$%^&?~`|\\\\€٣]|\\PC", 50),
+                unterminated in "(\"[^\"]*)?"
+            ) {
+                let mut source: String = tokens
+                    .iter()
+                    .zip(&invalid)
+                    .flat_map(|((_, lexeme), invalid)| [lexeme.as_str(), " ", invalid.as_str()])
+                    .collect();
+                source.push_str(&unterminated);
+                let mut scanner = Scanner::new(&source).with_trivia(true);
+                let lexemes: String = scanner
+                    .scan_tokens()
+                    .iter()
+                    .map(|t| t.lexeme.as_str())
+                    .collect();
+                prop_assert_eq!(source, lexemes);
+            }
+
+            #[test]
+            fn test_number_literal_matches_rust(
+                value in prop_oneof![
+                    0.0..f64::MAX,
+                    0.0..1000.0,
+                    (0u32..1_000_000).prop_map(|cents| f64::from(cents) / 100.0)
+                ]
+            ) {
+                let source = value.to_string();
+                let mut scanner = Scanner::new(&source);
+                let tokens = scanner.scan_tokens();
+                prop_assert_eq!(&TokenType::Number(value), &tokens[0].token_type);
+            }
+
+            #[test]
+            fn test_string_literal_matches_rust(value in "[^\"]*") {
+                let source = format!("\"{value}\"");
+                let mut scanner = Scanner::new(&source);
+                let tokens = scanner.scan_tokens();
+                prop_assert_eq!(&TokenType::String(value), &tokens[0].token_type);
+            }
+        }
+    }
+
+    #[test]
+    fn test_scan() {
+        assert_eq!(2, scan("nil").unwrap().len());
+        let errors = scan("@").unwrap_err();
+        assert_eq!(
+            "[line 1] Error[L0001]: Unexpected character.",
+            errors[0].to_string()
+        );
+    }
+
+    #[test]
+    fn test_scan_trivia() {
+        let source = "var a; // first\r\n\tprint a;\n";
+        let mut scanner = Scanner::new(source).with_trivia(true);
+        let tokens = scanner.scan_tokens();
+        let token_types: Vec<&TokenType> = tokens.iter().map(|t| &t.token_type).collect();
+        assert_eq!(
+            vec![
+                &TokenType::Var,
+                &TokenType::Whitespace,
+                &TokenType::Identifier("a".to_string()),
+                &TokenType::Semicolon,
+                &TokenType::Whitespace,
+                &TokenType::Comment,
+                &TokenType::Newline,
+                &TokenType::Whitespace,
+                &TokenType::Print,
+                &TokenType::Whitespace,
+                &TokenType::Identifier("a".to_string()),
+                &TokenType::Semicolon,
+                &TokenType::Newline,
+                &TokenType::Eof
+            ],
+            token_types
+        );
+
+        let lexemes: String = tokens.iter().map(|t| t.lexeme.as_str()).collect();
+        assert_eq!(source, lexemes);
+    }
+
+    #[test]
+    fn test_scan_trivia_keeps_errors() {
+        let limits = ScannerLimits {
+            max_identifier_length: 3,
+            max_tokens: 7,
+            ..Default::default()
+        };
+        let source = "a @ abcd \"open";
+        let mut scanner = Scanner::new(source)
+            .with_limits(limits)
+            .with_trivia(true);
+        let tokens = scanner.scan_tokens();
+        let invalid: Vec<&str> = tokens
+            .iter()
+            .filter(|t| t.token_type == TokenType::Invalid)
+            .map(|t| t.lexeme.as_str())
+            .collect();
+        assert_eq!(vec!["@", "abcd", "\"open"], invalid);
+
+        let lexemes: String = tokens.iter().map(|t| t.lexeme.as_str()).collect();
+        assert_eq!(source, lexemes);
+        assert_eq!(3, scanner.errors().len());
+    }
 }
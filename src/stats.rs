@@ -0,0 +1,125 @@
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Environment variable naming the stats file; recording is off unless it is set.
+pub const STATS_FILE_VAR: &str = "LOX_STATS_FILE";
+
+/// Timings and error count of one run.
+#[derive(Debug, Default, PartialEq)]
+pub struct RunStats {
+    pub phases: Vec<(String, Duration)>,
+    pub errors: usize,
+}
+
+/// Appends one line per run: time, script, error count, then `phase=micros` per phase.
+pub fn record(stats_file: &Path, script: &str, stats: &RunStats) -> Result<(), Box<dyn Error>> {
+    let time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let mut line = format!("{time}\t{}\t{}", script.replace('\t', " "), stats.errors);
+    for (phase, duration) in &stats.phases {
+        line.push_str(&format!("\t{phase}={}", duration.as_micros()));
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(stats_file)?;
+    writeln!(file, "{line}")?;
+    Ok(())
+}
+
+fn parse_line(line: &str) -> Option<RunStats> {
+    let mut fields = line.split('\t').skip(2);
+    let errors = fields.next()?.parse().ok()?;
+    let phases = fields
+        .map(|field| {
+            let (phase, micros) = field.split_once('=')?;
+            Some((
+                phase.to_string(),
+                Duration::from_micros(micros.parse().ok()?),
+            ))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(RunStats { phases, errors })
+}
+
+pub fn summarize(stats_file: &Path) -> Result<String, Box<dyn Error>> {
+    let content = fs::read_to_string(stats_file)?;
+    let runs: Vec<RunStats> = content.lines().filter_map(parse_line).collect();
+    Ok(summarize_runs(&runs))
+}
+
+fn summarize_runs(runs: &[RunStats]) -> String {
+    let failed_runs = runs.iter().filter(|run| run.errors > 0).count();
+    let errors: usize = runs.iter().map(|run| run.errors).sum();
+    let mut summary = format!(
+        "{} run(s), {failed_runs} with errors, {errors} error(s) in total\n",
+        runs.len()
+    );
+
+    // Phases in the order they first appear
+    let mut phases: Vec<&str> = Vec::new();
+    for (phase, _) in runs.iter().flat_map(|run| &run.phases) {
+        if !phases.contains(&phase.as_str()) {
+            phases.push(phase);
+        }
+    }
+
+    if !phases.is_empty() {
+        summary.push_str(&format!(
+            "{:<12} {:>6} {:>12} {:>12} {:>12}\n",
+            "phase", "runs", "total", "mean", "max"
+        ));
+    }
+    for phase in phases {
+        let durations: Vec<Duration> = runs
+            .iter()
+            .flat_map(|run| &run.phases)
+            .filter(|(name, _)| name == phase)
+            .map(|(_, duration)| *duration)
+            .collect();
+        let total: Duration = durations.iter().sum();
+        let max = durations.iter().max().copied().unwrap_or_default();
+        let mean = total / durations.len() as u32;
+        summary.push_str(&format!(
+            "{:<12} {:>6} {:>12.3?} {:>12.3?} {:>12.3?}\n",
+            phase,
+            durations.len(),
+            total,
+            mean,
+            max
+        ));
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(
+            Some(RunStats {
+                phases: vec![("scanning".to_string(), Duration::from_micros(120))],
+                errors: 2,
+            }),
+            parse_line("1700000000\ta.lox\t2\tscanning=120")
+        );
+        assert_eq!(None, parse_line("garbage"));
+    }
+
+    #[test]
+    fn test_summarize_runs() {
+        let runs = [
+            parse_line("1\ta.lox\t0\tscanning=100").unwrap(),
+            parse_line("2\tb.lox\t3\tscanning=300").unwrap(),
+        ];
+        let summary = summarize_runs(&runs);
+        assert!(summary.starts_with("2 run(s), 1 with errors, 3 error(s) in total\n"));
+        assert!(summary.contains("scanning          2"));
+        assert!(summary.contains("200.000µs"));
+    }
+}
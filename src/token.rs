@@ -1,21 +1,45 @@
 use std::fmt::{Display, Formatter};
 
+/// A lexeme of the source together with its meaning and position.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub struct Token {
     pub token_type: TokenType,
     pub lexeme: String,
+    pub file: FileId,
     pub line: usize,
+    pub span: Span,
 }
 
 impl Token {
-    pub fn new(token_type: TokenType, lexeme: &str, line: usize) -> Self {
+    pub fn new(
+        token_type: TokenType,
+        lexeme: &str,
+        file: FileId,
+        line: usize,
+        span: Span,
+    ) -> Self {
         Self {
             token_type,
             lexeme: lexeme.to_string(),
+            file,
             line,
+            span,
         }
     }
 }
 
+/// Identifies a source file among the files of one program
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FileId(pub usize);
+
+/// Byte range of a lexeme in the source
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
 impl Display for Token {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{} {}", self.token_type, self.lexeme)
@@ -24,12 +48,15 @@ impl Display for Token {
 
 #[derive(Debug, Clone)]
 #[derive(PartialEq)]
+#[non_exhaustive]
 pub enum TokenType {
     // Single-character tokens
     LeftParen,
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
     Dot,
     Minus,
@@ -55,22 +82,42 @@ pub enum TokenType {
 
     // Keywords
     And,
+    Break,
+    Case,
+    Catch,
     Class,
+    Const,
+    Continue,
+    Default,
     Else,
     False,
+    Finally,
     Fun,
     For,
     If,
+    Import,
+    In,
+    Macro,
     Nil,
     Or,
     Print,
     Return,
     Super,
+    Switch,
     This,
+    Throw,
     True,
+    Try,
     Var,
     While,
 
+    // Trivia, only emitted when the scanner is asked for it
+    Comment,
+    Whitespace,
+    Newline,
+    /// Source the scanner reported an error for
+    Invalid,
+
     Eof,
 }
 
@@ -81,6 +128,8 @@ impl Display for TokenType {
             TokenType::RightParen => f.write_str(")"),
             TokenType::LeftBrace => f.write_str("{"),
             TokenType::RightBrace => f.write_str("}"),
+            TokenType::LeftBracket => f.write_str("["),
+            TokenType::RightBracket => f.write_str("]"),
             TokenType::Comma => f.write_str(","),
             TokenType::Dot => f.write_str("."),
             TokenType::Minus => f.write_str("-"),
@@ -100,21 +149,38 @@ impl Display for TokenType {
             TokenType::String(str) => f.write_str(str),
             TokenType::Number(num) => f.write_str(&num.to_string()),
             TokenType::And => f.write_str("and"),
+            TokenType::Break => f.write_str("break"),
+            TokenType::Case => f.write_str("case"),
+            TokenType::Catch => f.write_str("catch"),
             TokenType::Class => f.write_str("class"),
+            TokenType::Const => f.write_str("const"),
+            TokenType::Continue => f.write_str("continue"),
+            TokenType::Default => f.write_str("default"),
             TokenType::Else => f.write_str("else"),
             TokenType::False => f.write_str("false"),
+            TokenType::Finally => f.write_str("finally"),
             TokenType::Fun => f.write_str("fun"),
             TokenType::For => f.write_str("for"),
             TokenType::If => f.write_str("if"),
+            TokenType::Import => f.write_str("import"),
+            TokenType::In => f.write_str("in"),
+            TokenType::Macro => f.write_str("macro"),
             TokenType::Nil => f.write_str("nil"),
             TokenType::Or => f.write_str("or"),
             TokenType::Print => f.write_str("print"),
             TokenType::Return => f.write_str("return"),
             TokenType::Super => f.write_str("super"),
+            TokenType::Switch => f.write_str("switch"),
             TokenType::This => f.write_str("this"),
+            TokenType::Throw => f.write_str("throw"),
             TokenType::True => f.write_str("true"),
+            TokenType::Try => f.write_str("try"),
             TokenType::Var => f.write_str("var"),
             TokenType::While => f.write_str("while"),
+            TokenType::Comment => f.write_str("//"),
+            TokenType::Whitespace => f.write_str(" "),
+            TokenType::Newline => f.write_str("\\n"),
+            TokenType::Invalid => f.write_str("invalid"),
             TokenType::Eof => f.write_str("\\d"),
         }
     }